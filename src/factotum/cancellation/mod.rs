@@ -0,0 +1,120 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+#[cfg(test)]
+mod tests;
+
+use libc;
+use std::cmp;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_GRACE_PERIOD_SECS: u64 = 10;
+const SLEEP_SLICE_MS: u64 = 50;
+
+// the signal that cancelled the job (0 if it hasn't been cancelled)
+static CANCEL_SIGNAL: AtomicUsize = AtomicUsize::new(0);
+static GRACE_PERIOD_SECS: AtomicUsize = AtomicUsize::new(DEFAULT_GRACE_PERIOD_SECS as usize);
+
+extern "C" fn handle_signal(signal: libc::c_int) {
+    // only async-signal-safe work can happen here, so the signal is just recorded for the
+    // executor (and any running tasks) to pick up
+    request_cancel(signal);
+    // a second signal means the user isn't prepared to wait for the tasks to stop, so it's left
+    // to kill factotum as it would have without the handlers
+    set_signal_handlers(libc::SIG_DFL);
+}
+
+fn set_signal_handlers(handler: libc::sighandler_t) {
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+pub fn install_signal_handlers() {
+    set_signal_handlers(handle_signal as libc::sighandler_t);
+}
+
+pub fn request_cancel(signal: i32) {
+    CANCEL_SIGNAL.store(signal as usize, Ordering::SeqCst);
+}
+
+pub fn cancel_requested() -> Option<i32> {
+    match CANCEL_SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal as i32),
+    }
+}
+
+// sleeps for the duration, or until the job is cancelled - returns false if it was cancelled
+pub fn sleep_unless_cancelled(duration: Duration) -> bool {
+    let started = Instant::now();
+    loop {
+        if cancel_requested().is_some() {
+            return false;
+        }
+        let elapsed = started.elapsed();
+        if elapsed >= duration {
+            return true;
+        }
+        thread::sleep(cmp::min(duration - elapsed, Duration::from_millis(SLEEP_SLICE_MS)));
+    }
+}
+
+pub fn set_grace_period(grace_period: Duration) {
+    GRACE_PERIOD_SECS.store(grace_period.as_secs() as usize, Ordering::SeqCst);
+}
+
+pub fn grace_period() -> Duration {
+    Duration::from_secs(GRACE_PERIOD_SECS.load(Ordering::SeqCst) as u64)
+}
+
+const SIGNALS: &'static [(&'static str, i32)] = &[("SIGHUP", libc::SIGHUP),
+                                                   ("SIGINT", libc::SIGINT),
+                                                   ("SIGQUIT", libc::SIGQUIT),
+                                                   ("SIGILL", libc::SIGILL),
+                                                   ("SIGTRAP", libc::SIGTRAP),
+                                                   ("SIGABRT", libc::SIGABRT),
+                                                   ("SIGBUS", libc::SIGBUS),
+                                                   ("SIGFPE", libc::SIGFPE),
+                                                   ("SIGKILL", libc::SIGKILL),
+                                                   ("SIGUSR1", libc::SIGUSR1),
+                                                   ("SIGSEGV", libc::SIGSEGV),
+                                                   ("SIGUSR2", libc::SIGUSR2),
+                                                   ("SIGPIPE", libc::SIGPIPE),
+                                                   ("SIGALRM", libc::SIGALRM),
+                                                   ("SIGTERM", libc::SIGTERM),
+                                                   ("SIGXCPU", libc::SIGXCPU),
+                                                   ("SIGXFSZ", libc::SIGXFSZ)];
+
+pub fn signal_name(signal: i32) -> String {
+    match SIGNALS.iter().find(|&&(_, number)| number == signal) {
+        Some(&(name, _)) => name.to_string(),
+        None => format!("signal {}", signal),
+    }
+}
+
+pub fn signal_number(name: &str) -> Option<i32> {
+    SIGNALS.iter().find(|&&(known, _)| known == name).map(|&(_, number)| number)
+}
+
+pub fn send_signal_to_group(pgid: u32, signal: i32) -> Result<(), String> {
+    if unsafe { libc::killpg(pgid as libc::pid_t, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(format!("couldn't send {} to process group {}", signal_name(signal), pgid))
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use super::*;
+use std::env;
+use std::io;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::Command;
+
+#[test]
+fn signal_names_are_readable() {
+    assert_eq!(signal_name(libc::SIGINT), "SIGINT");
+    assert_eq!(signal_name(libc::SIGTERM), "SIGTERM");
+    assert_eq!(signal_name(libc::SIGKILL), "SIGKILL");
+    assert_eq!(signal_name(12345), "signal 12345");
+}
+
+#[test]
+fn signal_numbers_found_by_name() {
+    assert_eq!(signal_number("SIGTERM"), Some(libc::SIGTERM));
+    assert_eq!(signal_number("SIGKILL"), Some(libc::SIGKILL));
+    assert_eq!(signal_number("SIGPOTATO"), None);
+    assert_eq!(signal_number("sigterm"), None);
+}
+
+#[test]
+fn send_signal_to_group_stops_a_process() {
+    let mut command = Command::new("sleep");
+    command.arg("30");
+    unsafe {
+        command.pre_exec(|| if libc::setpgid(0, 0) == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        });
+    }
+    let mut child = command.spawn().unwrap();
+    assert!(send_signal_to_group(child.id(), libc::SIGTERM).is_ok());
+    let status = child.wait().unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn second_signal_kills_factotum() {
+    // the handlers are process wide, so the signals are sent to a copy of this test binary
+    if env::var("FACTOTUM_TEST_SECOND_SIGNAL").is_ok() {
+        install_signal_handlers();
+        unsafe { libc::raise(libc::SIGINT) };
+        assert_eq!(cancel_requested(), Some(libc::SIGINT));
+        unsafe { libc::raise(libc::SIGINT) };
+        thread::sleep(Duration::from_secs(5));
+        return;
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&["--exact", "factotum::cancellation::tests::second_signal_kills_factotum"])
+        .env("FACTOTUM_TEST_SECOND_SIGNAL", "1")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.signal(),
+               Some(libc::SIGINT),
+               "{}",
+               String::from_utf8_lossy(&output.stdout));
+}
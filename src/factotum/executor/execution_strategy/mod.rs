@@ -14,8 +14,18 @@
 
 #[cfg(test)]
 mod tests;
-use std::process::Command;
+use std::process::{Command, Child, ExitStatus, Stdio};
 use std::time::{Instant, Duration};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::fs::{self, File};
+use std::path::Path;
+use std::thread;
+use factotum::cancellation;
+use factotum::tasklog::AttemptLogs;
+use libc;
+
+const TIMEOUT_POLL_INTERVAL_MS: u64 = 50;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct RunResult {
@@ -24,6 +34,11 @@ pub struct RunResult {
     pub stdout: Option<String>,
     pub stderr: Option<String>,
     pub return_code: i32,
+    pub timed_out: bool,
+    pub terminating_signal: Option<i32>,
+    pub leftover_processes: Option<String>,
+    pub logs: Option<AttemptLogs>,
+    pub previous_attempts: Vec<RunResult>,
 }
 
 pub fn simulation_text(name: &str, command: &Command) -> String {
@@ -49,7 +64,11 @@ pub fn simulation_text(name: &str, command: &Command) -> String {
     lines.join("\n")
 }
 
-pub fn execute_simulation(name: &str, command: &mut Command) -> RunResult {
+pub fn execute_simulation(name: &str,
+                          command: &mut Command,
+                          _: Option<Duration>,
+                          _: Option<&AttemptLogs>)
+                          -> RunResult {
     info!("Simulating execution for {} with command {:?}",
           name,
           command);
@@ -59,19 +78,264 @@ pub fn execute_simulation(name: &str, command: &mut Command) -> RunResult {
         stdout: Some(simulation_text(name, &command)),
         stderr: None,
         return_code: 0,
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     }
 }
 
-pub fn execute_os(name: &str, command: &mut Command) -> RunResult {
+pub fn prefix_line(name: &str, line: &[u8]) -> String {
+    let text = String::from_utf8_lossy(line);
+    format!("[{}] {}", name, text.trim_right_matches(|c| c == '\n' || c == '\r'))
+}
+
+// reads everything from the pipe, echoing each line to the console (prefixed with the task name)
+// as it arrives so long running tasks show their progress
+// each line is also written to the log file (if there is one) straight away, so nothing is lost
+// if factotum dies part way through the task
+fn read_pipe_async<R: Read + Send + 'static>(name: &str,
+                                             pipe: Option<R>,
+                                             to_stderr: bool,
+                                             log: Option<File>)
+                                             -> thread::JoinHandle<Vec<u8>> {
+    let name = name.to_string();
+    let mut log = log;
+    thread::spawn(move || {
+        let mut buffer = vec![];
+        if let Some(p) = pipe {
+            let mut reader = BufReader::new(p);
+            loop {
+                let mut line = vec![];
+                match reader.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        let prefixed = prefix_line(&name, &line);
+                        if to_stderr {
+                            writeln!(io::stderr(), "{}", prefixed).ok();
+                        } else {
+                            writeln!(io::stdout(), "{}", prefixed).ok();
+                        }
+                        if let Err(e) = log.as_mut().map_or(Ok(()), |f| f.write_all(&line)) {
+                            warn!("couldn't write the output of task '{}' to its log file: {}",
+                                  name,
+                                  e);
+                            log = None;
+                        }
+                        buffer.extend_from_slice(&line);
+                    }
+                    Err(e) => {
+                        warn!("couldn't read task output: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+        buffer
+    })
+}
+
+// waits for the child to exit, killing it if it's still running once the timeout has passed
+// if the job is cancelled the signal is passed on to the child, which is killed if it hasn't
+// exited by the end of the grace period
+// the bool returned is true if the child was killed for exceeding its timeout
+fn wait_with_timeout(child: &mut Child, timeout: Option<Duration>) -> io::Result<(ExitStatus, bool)> {
+    let started = Instant::now();
+    let mut cancelled_at: Option<Instant> = None;
+
+    loop {
+        if let Some(status) = try!(child.try_wait()) {
+            return Ok((status, false));
+        }
+
+        match cancelled_at {
+            None => {
+                if let Some(limit) = timeout {
+                    if started.elapsed() >= limit {
+                        // the child may have exited since we last checked, in which case kill() errs but wait() is still good
+                        cancellation::send_signal_to_group(child.id(), libc::SIGKILL).ok();
+                        child.kill().ok();
+                        let status = try!(child.wait());
+                        return Ok((status, true));
+                    }
+                }
+
+                if let Some(signal) = cancellation::cancel_requested() {
+                    if let Err(msg) = cancellation::send_signal_to_group(child.id(), signal) {
+                        warn!("{}", msg);
+                    }
+                    cancelled_at = Some(Instant::now());
+                }
+            }
+            Some(at) => {
+                if at.elapsed() >= cancellation::grace_period() {
+                    warn!("process group {} was still running at the end of the grace period, \
+                           killing it",
+                          child.id());
+                    cancellation::send_signal_to_group(child.id(), libc::SIGKILL).ok();
+                    let status = try!(child.wait());
+                    return Ok((status, false));
+                }
+            }
+        }
+
+        thread::sleep(Duration::from_millis(TIMEOUT_POLL_INTERVAL_MS));
+    }
+}
+
+// gets the process group from the contents of /proc/<pid>/stat, which looks like
+// "pid (name) state ppid pgrp ..." (the name can contain spaces and brackets)
+pub fn parse_process_group(stat: &str) -> Option<u32> {
+    match stat.rfind(')') {
+        Some(name_end) => {
+            stat[name_end + 1..].split_whitespace().nth(2).and_then(|pgrp| pgrp.parse().ok())
+        }
+        None => None,
+    }
+}
+
+fn get_process_group_pids(pgid: u32) -> Vec<u32> {
+    // only possible where there's a /proc, elsewhere we can't say which processes were left
+    let mut pids = vec![];
+
+    if let Ok(entries) = fs::read_dir("/proc") {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let pid = match entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            let mut stat = String::new();
+            let read = File::open(entry.path().join("stat"))
+                .and_then(|mut f| f.read_to_string(&mut stat));
+            if read.is_ok() && parse_process_group(&stat) == Some(pgid) {
+                pids.push(pid);
+            }
+        }
+    }
+
+    pids.sort();
+    pids
+}
+
+// kills anything the task started that's still running in its process group, returning a
+// description of what was left behind
+fn kill_process_group(pgid: u32) -> Option<String> {
+    // signal 0 only checks whether there's anything left in the group
+    if cancellation::send_signal_to_group(pgid, 0).is_err() {
+        return None;
+    }
+
+    let pids = get_process_group_pids(pgid);
+    if let Err(msg) = cancellation::send_signal_to_group(pgid, libc::SIGKILL) {
+        warn!("{}", msg);
+    }
+
+    if pids.is_empty() {
+        Some("factotum killed processes the task left running".to_string())
+    } else {
+        Some(format!("factotum killed {} process(es) the task left running (pids {})",
+                     pids.len(),
+                     pids.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(", ")))
+    }
+}
+
+// the task leads a new process group so that anything it starts in the background can be
+// signalled (and cleaned up) along with it
+pub fn run_in_new_process_group(command: &mut Command) -> &mut Command {
+    unsafe {
+        command.pre_exec(|| if libc::setpgid(0, 0) == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        })
+    }
+}
+
+fn open_log(name: &str, path: &Path) -> Option<File> {
+    match File::create(path) {
+        Ok(f) => Some(f),
+        Err(e) => {
+            warn!("couldn't create the log file '{}' for task '{}': {}",
+                  path.display(),
+                  name,
+                  e);
+            None
+        }
+    }
+}
+
+pub fn execute_os(name: &str,
+                  command: &mut Command,
+                  timeout: Option<Duration>,
+                  logs: Option<&AttemptLogs>)
+                  -> RunResult {
     let run_start = Instant::now();
-    info!("Executing sh {:?}", command);
-    match command.output() {
-        Ok(r) => {
+    info!("Executing {:?}", command);
+
+    // the log files are opened before the task starts so its output can be written as it arrives
+    let stdout_log = logs.and_then(|l| open_log(name, &l.stdout));
+    let stderr_log = logs.and_then(|l| open_log(name, &l.stderr));
+    let logs = if stdout_log.is_some() || stderr_log.is_some() {
+        logs.cloned()
+    } else {
+        None
+    };
+
+    command.stdin(Stdio::null());
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+
+    let mut child = match command.spawn() {
+        Ok(c) => c,
+        Err(message) => {
+            return RunResult {
+                duration: Duration::from_secs(0),
+                task_execution_error: Some(format!("Error executing process - {}", message)),
+                stdout: None,
+                stderr: None,
+                return_code: -1,
+                timed_out: false,
+                terminating_signal: None,
+                leftover_processes: None,
+                logs: None,
+                previous_attempts: vec![],
+            }
+        }
+    };
+
+    let stdout_reader = read_pipe_async(name, child.stdout.take(), false, stdout_log);
+    let stderr_reader = read_pipe_async(name, child.stderr.take(), true, stderr_log);
+
+    match wait_with_timeout(&mut child, timeout) {
+        Ok((status, timed_out)) => {
             let run_duration = run_start.elapsed();
-            let return_code = r.status.code().unwrap_or(1); // 1 will be returned if the process was killed by a signal
+            // a task killed by a signal gets the return code a shell would give it (128 + the signal)
+            let terminating_signal = status.signal();
+            let return_code = match (status.code(), terminating_signal) {
+                (Some(code), _) => code,
+                (None, Some(signal)) => 128 + signal,
+                (None, None) => 1,
+            };
+
+            if timed_out {
+                warn!("task '{}' was killed after exceeding its timeout of {:?}",
+                      name,
+                      timeout.unwrap());
+            }
+
+            // anything left in the group could hold the output pipes open, so it has to go
+            // before the output can be collected
+            let leftovers = kill_process_group(child.id());
+            if let Some(ref msg) = leftovers {
+                warn!("task '{}': {}", name, msg);
+            }
+
+            let stdout = stdout_reader.join().unwrap_or(vec![]);
+            let stderr = stderr_reader.join().unwrap_or(vec![]);
 
-            let task_stdout: String = String::from_utf8_lossy(&r.stdout).trim_right().into();
-            let task_stderr: String = String::from_utf8_lossy(&r.stderr).trim_right().into();
+            let task_stdout: String = String::from_utf8_lossy(&stdout).trim_right().into();
+            let task_stderr: String = String::from_utf8_lossy(&stderr).trim_right().into();
 
             info!("task '{}' stdout:\n'{}'", name, task_stdout);
             info!("task '{}' stderr:\n'{}'", name, task_stderr);
@@ -93,15 +357,26 @@ pub fn execute_os(name: &str, command: &mut Command) -> RunResult {
                 stdout: task_stdout_opt,
                 stderr: task_stderr_opt,
                 return_code: return_code,
+                timed_out: timed_out,
+                terminating_signal: terminating_signal,
+                leftover_processes: leftovers,
+                logs: logs,
+                previous_attempts: vec![],
             }
         }
         Err(message) => {
+            kill_process_group(child.id());
             RunResult {
-                duration: Duration::from_secs(0),
-                task_execution_error: Some(format!("Error executing process - {}", message)),
+                duration: run_start.elapsed(),
+                task_execution_error: Some(format!("Error waiting for process - {}", message)),
                 stdout: None,
                 stderr: None,
                 return_code: -1,
+                timed_out: false,
+                terminating_signal: None,
+                leftover_processes: None,
+                logs: logs,
+                previous_attempts: vec![],
             }
         }
     }
@@ -80,7 +80,7 @@ fn simulation_text_good() {
 fn simulation_returns_good() {
     let mut command: Command = Command::new("banana");
     command.arg("hello_world");
-    let result = execute_simulation("hello-world", &mut command);
+    let result = execute_simulation("hello-world", &mut command, None, None);
 
     assert_eq!(result.return_code, 0);
     assert_eq!(result.duration, Duration::seconds(0).to_std().ok().unwrap());
@@ -94,7 +94,7 @@ fn os_execution_notfound() {
     let mut command: Command = Command::new("sh");
     command.arg("-c");
     command.arg("banana");
-    let result = execute_os("hello-world", &mut command);
+    let result = execute_os("hello-world", &mut command, None, None);
 
     assert_eq!(result.return_code, 127);
     assert_eq!(result.duration.as_secs(), 0);
@@ -109,7 +109,7 @@ fn os_execution_notfound() {
 #[test]
 fn os_execution_task_exec_failed() {
     let mut command: Command = Command::new("this-doesn't-exist");
-    let result = execute_os("hello-world", &mut command);
+    let result = execute_os("hello-world", &mut command, None, None);
 
     assert_eq!(result.return_code, -1);
     assert_eq!(result.duration.as_secs(), 0);
@@ -125,7 +125,7 @@ fn os_execution_good() {
     let mut command: Command = Command::new("sh");
     command.arg("-c");
     command.arg("type echo");
-    let result = execute_os("hello-world", &mut command);
+    let result = execute_os("hello-world", &mut command, None, None);
 
     assert_eq!(result.return_code, 0);
     assert_eq!(result.duration.as_secs(), 0);
@@ -133,3 +133,145 @@ fn os_execution_good() {
     assert_eq!(result.stdout.unwrap(), "echo is a shell builtin");
     assert_eq!(result.task_execution_error, None);
 }
+
+#[test]
+fn os_execution_timeout_kills_task() {
+    use std::time::Duration as StdDuration;
+
+    let mut command: Command = Command::new("sleep");
+    command.arg("10");
+    let result = execute_os("hello-world", &mut command, Some(StdDuration::from_millis(200)), None);
+
+    assert!(result.timed_out);
+    assert!(result.duration < StdDuration::from_secs(10));
+    assert_eq!(result.task_execution_error, None);
+}
+
+#[test]
+fn os_execution_within_timeout() {
+    use std::time::Duration as StdDuration;
+
+    let mut command: Command = Command::new("sh");
+    command.arg("-c");
+    command.arg("echo hello");
+    let result = execute_os("hello-world", &mut command, Some(StdDuration::from_secs(10)), None);
+
+    assert!(!result.timed_out);
+    assert_eq!(result.return_code, 0);
+    assert_eq!(result.stdout.unwrap(), "hello");
+}
+
+#[test]
+fn output_lines_prefixed_with_task_name() {
+    assert_eq!(prefix_line("apple", b"hello world\n"), "[apple] hello world");
+    assert_eq!(prefix_line("apple", b"windows\r\n"), "[apple] windows");
+    assert_eq!(prefix_line("apple", b"no newline"), "[apple] no newline");
+}
+
+#[test]
+fn os_execution_keeps_streamed_output() {
+    let mut command = Command::new("sh");
+    command.arg("-c");
+    command.arg("echo one; echo two >&2; echo three");
+    let result = execute_os("streaming", &mut command, None, None);
+    assert_eq!(result.stdout, Some("one\nthree".to_string()));
+    assert_eq!(result.stderr, Some("two".to_string()));
+}
+
+#[test]
+fn os_execution_writes_output_to_log_files() {
+    use factotum::tasklog::AttemptLogs;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Read;
+
+    let mut log_dir = env::temp_dir();
+    log_dir.push("factotum-execute-os-test-logs");
+    fs::remove_dir_all(&log_dir).ok();
+    fs::create_dir_all(&log_dir).unwrap();
+    let logs = AttemptLogs {
+        stdout: log_dir.join("streaming.1.out"),
+        stderr: log_dir.join("streaming.1.err"),
+    };
+
+    let mut command = Command::new("sh");
+    command.arg("-c");
+    command.arg("echo one; echo two >&2; echo three");
+    let result = execute_os("streaming", &mut command, None, Some(&logs));
+
+    let mut stdout = String::new();
+    File::open(&logs.stdout).unwrap().read_to_string(&mut stdout).unwrap();
+    let mut stderr = String::new();
+    File::open(&logs.stderr).unwrap().read_to_string(&mut stderr).unwrap();
+    assert_eq!(stdout, "one\nthree\n");
+    assert_eq!(stderr, "two\n");
+    assert_eq!(result.logs, Some(logs));
+
+    fs::remove_dir_all(&log_dir).ok();
+}
+
+#[test]
+fn os_execution_without_log_files() {
+    let mut command = Command::new("sh");
+    command.arg("-c");
+    command.arg("echo hello");
+    let result = execute_os("hello-world", &mut command, None, None);
+    assert_eq!(result.logs, None);
+}
+
+#[test]
+fn process_group_parsed_from_proc_stat() {
+    assert_eq!(parse_process_group("1234 (sleep) S 1 1230 1230 0 -1 4194560"),
+               Some(1230));
+    assert_eq!(parse_process_group("1234 (odd) name)) R 1 99 99 0"), Some(99));
+    assert_eq!(parse_process_group("garbage"), None);
+}
+
+#[test]
+fn os_execution_kills_background_processes() {
+    use std::time::Duration as StdDuration;
+
+    let mut command = Command::new("sh");
+    command.arg("-c");
+    command.arg("sleep 30 & echo started");
+    let result = execute_os("background",
+                            run_in_new_process_group(&mut command),
+                            None,
+                            None);
+
+    assert_eq!(result.return_code, 0);
+    assert!(result.duration < StdDuration::from_secs(30));
+    assert_eq!(result.stdout, Some("started".to_string()));
+    assert_eq!(result.stderr, None);
+    assert!(result.leftover_processes.unwrap().contains("left running"));
+}
+
+#[test]
+fn os_execution_without_leftovers() {
+    let mut command = Command::new("sh");
+    command.arg("-c");
+    command.arg("echo done");
+    let result = execute_os("tidy", run_in_new_process_group(&mut command), None, None);
+
+    assert_eq!(result.leftover_processes, None);
+}
+
+#[test]
+fn os_execution_has_no_stdin() {
+    let mut command = Command::new("cat");
+    let result = execute_os("reader", &mut command, None, None);
+
+    assert_eq!(result.return_code, 0);
+    assert_eq!(result.stdout, None);
+}
+
+#[test]
+fn os_execution_records_terminating_signal() {
+    let mut command = Command::new("sh");
+    command.arg("-c");
+    command.arg("kill -TERM $$");
+    let result = execute_os("signalled", &mut command, None, None);
+
+    assert_eq!(result.terminating_signal, Some(15));
+    assert_eq!(result.return_code, 128 + 15);
+}
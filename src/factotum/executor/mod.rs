@@ -19,12 +19,18 @@ mod tests;
 
 use factotum::executor::task_list::*;
 use factotum::executor::execution_strategy::*;
+use factotum::cancellation;
+use factotum::tasklog;
+use factotum::tasklog::AttemptLogs;
 use chrono::UTC;
 use factotum::factfile::Task as FactfileTask;
-use factotum::factfile::Factfile;
+use factotum::factfile::{self, Factfile, RetryPolicy};
 use std::process::Command;
 use std::thread;
 use std::sync::mpsc;
+use std::time::Duration;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 pub fn get_task_execution_list(factfile: &Factfile,
                                start_from: Option<String>)
@@ -162,14 +168,22 @@ pub fn get_task_snapshot(tasklist: &TaskList<&FactfileTask>) -> TaskSnapshot {
 
 pub fn execute_factfile<'a, F>(factfile: &'a Factfile,
                                start_from: Option<String>,
+                               max_parallel: Option<usize>,
+                               completed_tasks: Option<HashMap<String, State>>,
                                strategy: F,
-                               progress_channel: Option<mpsc::Sender<ExecutionUpdate>>)
+                               progress_channel: Option<mpsc::Sender<ExecutionUpdate>>,
+                               log_dir: Option<PathBuf>)
                                -> TaskList<&'a FactfileTask>
-    where F: Fn(&str, &mut Command) -> RunResult + Send + Sync + 'static + Copy
+    where F: Fn(&str, &mut Command, Option<Duration>, Option<&AttemptLogs>) -> RunResult
+             + Send + Sync + 'static + Copy
 {
 
     let mut tasklist = get_task_execution_list(factfile, start_from);
 
+    if let Some(ref completed) = completed_tasks {
+        restore_completed_tasks(&mut tasklist, completed);
+    }
+
     // notify the progress channel
     if let Some(ref send) = progress_channel {
         let update =
@@ -180,46 +194,49 @@ pub fn execute_factfile<'a, F>(factfile: &'a Factfile,
         send.send(update).unwrap();
     }
 
-    for task_grp_idx in 0..tasklist.tasks.len() {
-        // everything in a task "group" gets run together
-        let (tx, rx) = mpsc::channel::<(usize, RunResult)>();
-
-        {
-            let ref mut task_group = tasklist.tasks[task_grp_idx];
-            for (idx, task) in task_group.into_iter().enumerate() {
-
-                if task.state == State::Waiting {
-                    info!("Running task '{}'!", task.name);
-                    task.state = State::Running;
-                    task.run_started = Some(UTC::now());
-                    {
-                        let tx = tx.clone();
-                        let args = format_args(&task.task_spec.command, &task.task_spec.arguments);
-                        let task_name = task.name.to_string();
-
-                        thread::spawn(move || {
-                            let mut command = Command::new("sh");
-                            command.arg("-c");
-                            command.arg(args);
-                            let task_result = strategy(&task_name, &mut command);
-                            tx.send((idx, task_result)).unwrap();
-                        });
-                    }
-                } else {
-                    info!("Skipped task '{}'", task.name);
-                }
-
+    // tasks are started as soon as all the tasks they depend on have succeeded,
+    // rather than waiting for everything in the previous task "group" to finish
+    let (tx, rx) = mpsc::channel::<((usize, usize), RunResult)>();
+    let mut running_count = 0;
+    let mut is_first_run = !tasklist.tasks.is_empty();
+
+    loop {
+        // once the job has been cancelled nothing new is started, we just wait for the running
+        // tasks to stop
+        let ready_tasks = if cancellation::cancel_requested().is_some() {
+            vec![]
+        } else {
+            get_ready_tasks(&tasklist, max_parallel, &factfile.pools)
+        };
+
+        for &(task_grp_idx, idx) in ready_tasks.iter() {
+            let ref mut task = tasklist.tasks[task_grp_idx][idx];
+            info!("Running task '{}'!", task.name);
+            task.state = State::Running;
+            task.run_started = Some(UTC::now());
+            {
+                let tx = tx.clone();
+                let task_name = task.name.to_string();
+                let task_spec = task.task_spec.clone();
+                let log_dir = log_dir.clone();
+
+                thread::spawn(move || {
+                    let mut command = get_task_command(&task_spec);
+                    let task_result = execute_with_retries(&task_name,
+                                                           &mut command,
+                                                           task_spec.timeout,
+                                                           task_spec.retry.clone(),
+                                                           log_dir,
+                                                           strategy);
+                    tx.send(((task_grp_idx, idx), task_result)).unwrap();
+                });
             }
         }
 
-        let expected_count = tasklist.tasks[task_grp_idx]
-            .iter()
-            .filter(|t| t.state == State::Running)
-            .count();
-
-        let is_first_run = task_grp_idx == 0;
+        running_count += ready_tasks.len();
 
         if is_first_run {
+            is_first_run = false;
             if let Some(ref send) = progress_channel {
                 let update = ExecutionUpdate::new(ExecutionState::Running, 
                                           get_task_snapshot(&tasklist),
@@ -228,14 +245,11 @@ pub fn execute_factfile<'a, F>(factfile: &'a Factfile,
             }
         }
 
-        if expected_count > 0 {
-
+        if ready_tasks.len() > 0 {
             if let Some(ref send) = progress_channel {
-                let running_task_transitions = tasklist.tasks[task_grp_idx]
-                    .iter()
-                    .filter(|t| t.state == State::Running)
-                    .map(|t| {
-                        TaskTransition::new(&t.name,
+                let running_task_transitions = ready_tasks.iter()
+                    .map(|&(task_grp_idx, idx)| {
+                        TaskTransition::new(&tasklist.tasks[task_grp_idx][idx].name,
                                             TaskExecutionState::Waiting,
                                             TaskExecutionState::Running)
                     })
@@ -247,107 +261,134 @@ pub fn execute_factfile<'a, F>(factfile: &'a Factfile,
 
                 send.send(update).unwrap();
             }
+        }
 
-            for _ in 0..expected_count {
-                let (idx, task_result) = rx.recv().unwrap();
-
-                info!("'{}' returned {} in {:?}",
-                      tasklist.tasks[task_grp_idx][idx].name,
-                      task_result.return_code,
-                      task_result.duration);
-
-                let mut additional_transitions = vec![];
-
-                if tasklist.tasks[task_grp_idx][idx]
-                    .task_spec
-                    .on_result
-                    .terminate_job
-                    .contains(&task_result.return_code) {
-                    // if the return code is in the terminate early list, prune the sub-tree (set to skipped) return early term
-                    tasklist.tasks[task_grp_idx][idx].state = State::SuccessNoop;
-
-                    let skip_list =
-                        tasklist.get_descendants(&tasklist.tasks[task_grp_idx][idx].name);
-
-                    let cause_task = tasklist.tasks[task_grp_idx][idx].name.clone();
-
-                    for mut task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
-                        // all the tasks
-                        if skip_list.contains(&task.name) {
-                            let skip_message = if let State::Skipped(ref msg) = task.state {
-                                format!("{}, the task '{}' requested early termination",
-                                        msg,
-                                        &cause_task)
-                            } else {
-                                format!("the task '{}' requested early termination", &cause_task)
-                            };
-                            let prev_state = task.state.clone();
-                            task.state = State::Skipped(skip_message);
-                            let skip_transition =
-                                TaskTransition::new(&task.name, prev_state, task.state.clone());
-                            additional_transitions.push(skip_transition);
-                        }
-                    }
-                } else if tasklist.tasks[task_grp_idx][idx]
-                    .task_spec
-                    .on_result
-                    .continue_job
-                    .contains(&task_result.return_code) {
-                    // if the return code is in the continue list, return success
-                    tasklist.tasks[task_grp_idx][idx].state = State::Success;
-                } else {
-                    // if the return code is not in either list, prune the sub-tree (set to skipped) and return error
-                    let expected_codes = tasklist.tasks[task_grp_idx][idx]
-                        .task_spec
-                        .on_result
-                        .continue_job
-                        .iter()
-                        .map(|code| code.to_string())
-                        .collect::<Vec<String>>()
-                        .join(",");
-                    let err_msg = format!("the task exited with a value not specified in \
-                                           continue_job - {} (task expects one of the following \
-                                           return codes to continue [{}])",
-                                          task_result.return_code,
-                                          expected_codes);
-                    tasklist.tasks[task_grp_idx][idx].state = State::Failed(err_msg);
-                    let skip_list =
-                        tasklist.get_descendants(&tasklist.tasks[task_grp_idx][idx].name);
-
-                    let cause_task = tasklist.tasks[task_grp_idx][idx].name.clone();
-
-                    for mut task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
-                        // all the tasks
-                        if skip_list.contains(&task.name) {
-                            let skip_message = if let State::Skipped(ref msg) = task.state {
-                                format!("{}, the task '{}' failed", msg, cause_task)
-                            } else {
-                                format!("the task '{}' failed", cause_task)
-                            };
-                            let prev_state = task.state.clone();
-                            task.state = State::Skipped(skip_message);
-                            let skip_transition =
-                                TaskTransition::new(&task.name, prev_state, task.state.clone());
-                            additional_transitions.push(skip_transition);
-                        }
-                    }
+        if running_count == 0 {
+            // nothing is running and nothing else can be started
+            break;
+        }
+
+        let ((task_grp_idx, idx), task_result) = rx.recv().unwrap();
+        running_count -= 1;
+
+        info!("'{}' returned {} in {:?}",
+              tasklist.tasks[task_grp_idx][idx].name,
+              task_result.return_code,
+              task_result.duration);
+
+        let mut additional_transitions = vec![];
+
+        // a task that was still running when the job was cancelled is cancelled, unless it
+        // managed to finish what it was doing
+        let finished_cleanly = {
+            let ref on_result = tasklist.tasks[task_grp_idx][idx].task_spec.on_result;
+            task_result.task_execution_error.is_none() && !task_result.timed_out &&
+            (on_result.continues_job(task_result.return_code, task_result.terminating_signal) ||
+             on_result.terminates_job(task_result.return_code, task_result.terminating_signal))
+        };
+        let cancelled_by = if finished_cleanly {
+            None
+        } else {
+            cancellation::cancel_requested()
+        };
+
+        if let Some(signal) = cancelled_by {
+            let reason = format!("the job was cancelled by {}",
+                                 cancellation::signal_name(signal));
+            additional_transitions = cancel_task(&mut tasklist, task_grp_idx, idx, reason);
+        } else if task_result.timed_out {
+            // a task that had to be killed has failed, whatever it returned
+            let timeout_secs = tasklist.tasks[task_grp_idx][idx]
+                .task_spec
+                .timeout
+                .map(|t| t.as_secs())
+                .unwrap_or(0);
+            let err_msg = format!("the task exceeded its timeout of {}s and was killed",
+                                  timeout_secs);
+            additional_transitions = fail_task(&mut tasklist, task_grp_idx, idx, err_msg);
+        } else if tasklist.tasks[task_grp_idx][idx]
+            .task_spec
+            .on_result
+            .terminates_job(task_result.return_code, task_result.terminating_signal) {
+            // if the return code (or signal) is in the terminate early list, prune the sub-tree (set to skipped) return early term
+            tasklist.tasks[task_grp_idx][idx].state = State::SuccessNoop;
+
+            let skip_list = tasklist.get_descendants(&tasklist.tasks[task_grp_idx][idx].name);
+
+            let cause_task = tasklist.tasks[task_grp_idx][idx].name.clone();
+
+            for mut task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
+                // all the tasks
+                if skip_list.contains(&task.name) {
+                    let skip_message = if let State::Skipped(ref msg) = task.state {
+                        format!("{}, the task '{}' requested early termination",
+                                msg,
+                                &cause_task)
+                    } else {
+                        format!("the task '{}' requested early termination", &cause_task)
+                    };
+                    let prev_state = task.state.clone();
+                    task.state = State::Skipped(skip_message);
+                    let skip_transition =
+                        TaskTransition::new(&task.name, prev_state, task.state.clone());
+                    additional_transitions.push(skip_transition);
                 }
+            }
+        } else if tasklist.tasks[task_grp_idx][idx]
+            .task_spec
+            .on_result
+            .continues_job(task_result.return_code, task_result.terminating_signal) {
+            // if the return code (or signal) is in the continue list, return success
+            tasklist.tasks[task_grp_idx][idx].state = State::Success;
+        } else if let Some(signal) = task_result.terminating_signal {
+            // a task killed by a signal that isn't mapped in onSignal has failed
+            let err_msg = format!("the task was killed by {}, which isn't specified in onSignal",
+                                  cancellation::signal_name(signal));
+            additional_transitions = fail_task(&mut tasklist, task_grp_idx, idx, err_msg);
+        } else {
+            // if the return code is not in either list, prune the sub-tree (set to skipped) and return error
+            let expected_codes = tasklist.tasks[task_grp_idx][idx]
+                .task_spec
+                .on_result
+                .continue_job
+                .iter()
+                .map(|code| code.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+            let err_msg = format!("the task exited with a value not specified in \
+                                   continue_job - {} (task expects one of the following \
+                                   return codes to continue [{}])",
+                                  task_result.return_code,
+                                  expected_codes);
+            additional_transitions = fail_task(&mut tasklist, task_grp_idx, idx, err_msg);
+        }
 
-                tasklist.tasks[task_grp_idx][idx].run_result = Some(task_result);
+        tasklist.tasks[task_grp_idx][idx].run_result = Some(task_result);
 
-                if let Some(ref send) = progress_channel {
-                    let exec_task_transition =
-                        TaskTransition::new(&tasklist.tasks[task_grp_idx][idx].name,
-                                            TaskExecutionState::Running,
-                                            tasklist.tasks[task_grp_idx][idx].state.clone());
-                    additional_transitions.insert(0, exec_task_transition);
-
-                    let update = ExecutionUpdate::new(ExecutionState::Running,
-                                                      get_task_snapshot(&tasklist),
-                                                      Transition::Task(additional_transitions));
-                    send.send(update).unwrap();
-                }
+        if let Some(ref send) = progress_channel {
+            let exec_task_transition =
+                TaskTransition::new(&tasklist.tasks[task_grp_idx][idx].name,
+                                    TaskExecutionState::Running,
+                                    tasklist.tasks[task_grp_idx][idx].state.clone());
+            additional_transitions.insert(0, exec_task_transition);
+
+            let update = ExecutionUpdate::new(ExecutionState::Running,
+                                              get_task_snapshot(&tasklist),
+                                              Transition::Task(additional_transitions));
+            send.send(update).unwrap();
+        }
+    }
 
+    if let Some(signal) = cancellation::cancel_requested() {
+        let skip_transitions = skip_waiting_tasks(&mut tasklist,
+                                                  format!("the job was cancelled by {}",
+                                                          cancellation::signal_name(signal)));
+        if !skip_transitions.is_empty() {
+            if let Some(ref send) = progress_channel {
+                let update = ExecutionUpdate::new(ExecutionState::Running,
+                                                  get_task_snapshot(&tasklist),
+                                                  Transition::Task(skip_transitions));
+                send.send(update).unwrap();
             }
         }
     }
@@ -362,6 +403,237 @@ pub fn execute_factfile<'a, F>(factfile: &'a Factfile,
     tasklist
 }
 
+fn restore_completed_tasks(tasklist: &mut TaskList<&FactfileTask>,
+                           completed: &HashMap<String, State>) {
+    // tasks that finished in a previous run keep their state and aren't run again; anything
+    // below a task that requested early termination stays skipped
+    let mut noop_tasks = vec![];
+
+    for task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
+        match completed.get(&task.name) {
+            Some(&State::Success) => task.state = State::Success,
+            Some(&State::SuccessNoop) => {
+                task.state = State::SuccessNoop;
+                noop_tasks.push(task.name.clone());
+            }
+            _ => (),
+        }
+    }
+
+    for cause_task in noop_tasks.iter() {
+        let skip_list = tasklist.get_descendants(cause_task);
+        for task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
+            if skip_list.contains(&task.name) && task.state == State::Waiting {
+                task.state = State::Skipped(format!("the task '{}' requested early termination",
+                                                    cause_task));
+            }
+        }
+    }
+}
+
+fn get_ready_tasks(tasklist: &TaskList<&FactfileTask>,
+                   max_parallel: Option<usize>,
+                   pools: &HashMap<String, usize>)
+                   -> Vec<(usize, usize)> {
+    // a task is ready when it's waiting and every task it depends on has succeeded
+    // (tasks that depend on a failed or no-op task will already have been skipped)
+    // and starting it wouldn't take the job, or the task's pool, over its limit
+    let has_succeeded = |name: &str| {
+        tasklist.tasks
+            .iter()
+            .flat_map(|tg| tg.iter())
+            .any(|t| t.name == name && t.state == State::Success)
+    };
+
+    let mut running_count = 0;
+    let mut pool_usage: HashMap<&str, usize> = HashMap::new();
+
+    for task in tasklist.tasks.iter().flat_map(|tg| tg.iter()) {
+        if task.state == State::Running {
+            running_count += 1;
+            if let Some(ref pool) = task.task_spec.pool {
+                *pool_usage.entry(pool.as_str()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut ready = vec![];
+
+    for (task_grp_idx, task_group) in tasklist.tasks.iter().enumerate() {
+        for (idx, task) in task_group.iter().enumerate() {
+            if task.state != State::Waiting ||
+               !tasklist.get_parents(&task.name).iter().all(|parent| has_succeeded(parent)) {
+                continue;
+            }
+
+            if let Some(max) = max_parallel {
+                if running_count >= max {
+                    return ready;
+                }
+            }
+
+            if let Some(ref pool) = task.task_spec.pool {
+                let limit = pools.get(pool).cloned().unwrap_or(usize::max_value());
+                let used = pool_usage.entry(pool.as_str()).or_insert(0);
+                if *used >= limit {
+                    continue;
+                }
+                *used += 1;
+            }
+
+            running_count += 1;
+            ready.push((task_grp_idx, idx));
+        }
+    }
+
+    ready
+}
+
+fn execute_with_retries<F>(task_name: &str,
+                           command: &mut Command,
+                           timeout: Option<Duration>,
+                           retry: Option<RetryPolicy>,
+                           log_dir: Option<PathBuf>,
+                           strategy: F)
+                           -> RunResult
+    where F: Fn(&str, &mut Command, Option<Duration>, Option<&AttemptLogs>) -> RunResult
+{
+    // each attempt gets its own log files, so earlier attempts (including those from a run
+    // that's being resumed) are never overwritten
+    let attempt_logs = || log_dir.as_ref().map(|dir| tasklog::next_attempt_logs(dir, task_name));
+
+    let logs = attempt_logs();
+    let mut task_result = strategy(task_name, command, timeout, logs.as_ref());
+
+    if let Some(policy) = retry {
+        let mut previous_attempts = vec![];
+        let mut attempt = 1;
+
+        while attempt < policy.max_attempts && task_result.task_execution_error.is_none() &&
+              !task_result.timed_out && cancellation::cancel_requested().is_none() &&
+              policy.retry_on.contains(&task_result.return_code) {
+            attempt += 1;
+            let delay = policy.delay_before_attempt(attempt);
+            info!("'{}' returned {}, retrying in {:?} (attempt {} of {})",
+                  task_name,
+                  task_result.return_code,
+                  delay,
+                  attempt,
+                  policy.max_attempts);
+            if !cancellation::sleep_unless_cancelled(delay) {
+                info!("'{}' won't be retried as the job was cancelled", task_name);
+                break;
+            }
+            previous_attempts.push(task_result);
+            let logs = attempt_logs();
+            task_result = strategy(task_name, command, timeout, logs.as_ref());
+        }
+
+        task_result.previous_attempts = previous_attempts;
+    }
+
+    task_result
+}
+
+fn fail_task(tasklist: &mut TaskList<&FactfileTask>,
+             task_grp_idx: usize,
+             idx: usize,
+             reason: String)
+             -> Vec<TaskTransition> {
+    // set the task to failed, prune the sub-tree (set to skipped) and return the skip transitions
+    let mut transitions = vec![];
+
+    tasklist.tasks[task_grp_idx][idx].state = State::Failed(reason);
+    let skip_list = tasklist.get_descendants(&tasklist.tasks[task_grp_idx][idx].name);
+
+    let cause_task = tasklist.tasks[task_grp_idx][idx].name.clone();
+
+    for mut task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
+        // all the tasks
+        if skip_list.contains(&task.name) {
+            let skip_message = if let State::Skipped(ref msg) = task.state {
+                format!("{}, the task '{}' failed", msg, cause_task)
+            } else {
+                format!("the task '{}' failed", cause_task)
+            };
+            let prev_state = task.state.clone();
+            task.state = State::Skipped(skip_message);
+            let skip_transition = TaskTransition::new(&task.name, prev_state, task.state.clone());
+            transitions.push(skip_transition);
+        }
+    }
+
+    transitions
+}
+
+fn cancel_task(tasklist: &mut TaskList<&FactfileTask>,
+               task_grp_idx: usize,
+               idx: usize,
+               reason: String)
+               -> Vec<TaskTransition> {
+    // set the task to cancelled, prune the sub-tree (set to skipped) and return the skip transitions
+    let mut transitions = vec![];
+
+    tasklist.tasks[task_grp_idx][idx].state = State::Cancelled(reason);
+    let skip_list = tasklist.get_descendants(&tasklist.tasks[task_grp_idx][idx].name);
+
+    let cause_task = tasklist.tasks[task_grp_idx][idx].name.clone();
+
+    for task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
+        if skip_list.contains(&task.name) {
+            let skip_message = if let State::Skipped(ref msg) = task.state {
+                format!("{}, the task '{}' was cancelled", msg, cause_task)
+            } else {
+                format!("the task '{}' was cancelled", cause_task)
+            };
+            let prev_state = task.state.clone();
+            task.state = State::Skipped(skip_message);
+            transitions.push(TaskTransition::new(&task.name, prev_state, task.state.clone()));
+        }
+    }
+
+    transitions
+}
+
+fn skip_waiting_tasks(tasklist: &mut TaskList<&FactfileTask>, reason: String) -> Vec<TaskTransition> {
+    // tasks that never got the chance to start are skipped
+    let mut transitions = vec![];
+
+    for task in tasklist.tasks.iter_mut().flat_map(|tg| tg.iter_mut()) {
+        if task.state == State::Waiting {
+            task.state = State::Skipped(reason.clone());
+            transitions.push(TaskTransition::new(&task.name, State::Waiting, task.state.clone()));
+        }
+    }
+
+    transitions
+}
+
+pub fn get_task_command(task_spec: &FactfileTask) -> Command {
+    // exec tasks get their arguments as they are, shell tasks have them quoted into the command
+    // line given to sh
+    let mut command = if task_spec.executor == factfile::EXEC_EXECUTOR {
+        let mut command = Command::new(&task_spec.command);
+        command.args(&task_spec.arguments);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command.arg(format_args(&task_spec.command, &task_spec.arguments));
+        command
+    };
+
+    for (key, value) in task_spec.env.iter() {
+        command.env(key, value);
+    }
+    if let Some(ref dir) = task_spec.working_directory {
+        command.current_dir(dir);
+    }
+    execution_strategy::run_in_new_process_group(&mut command);
+
+    command
+}
+
 pub fn format_args(command: &str, args: &Vec<String>) -> String {
     let arg_str = args.iter()
         .map(|s| format!("\"{}\"", s))
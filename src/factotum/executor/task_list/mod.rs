@@ -19,7 +19,7 @@ use factotum::executor::execution_strategy::RunResult;
 use chrono::UTC;
 use chrono::DateTime;
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, RustcEncodable, RustcDecodable)]
 pub enum State {
     Waiting,
     Running,
@@ -27,6 +27,7 @@ pub enum State {
     SuccessNoop,
     Failed(String),
     Skipped(String),
+    Cancelled(String),
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -122,6 +123,16 @@ impl<T> TaskList<T> {
         descendants
     }
 
+    pub fn get_parents(&self, task_name: &str) -> Vec<String> {
+        let mut parents = self.edges
+            .iter()
+            .filter(|&(_, children)| children.iter().any(|child| child == task_name))
+            .map(|(parent, _)| parent.clone())
+            .collect::<Vec<String>>();
+        parents.sort();
+        parents
+    }
+
     fn get_descendants_recursively(&self, task_name: &str) -> Vec<String> {
         let default = &vec![];
         let deps: Vec<String> =
@@ -139,6 +139,23 @@ fn get_children_dups_removed() {
     assert_eq!(Vec::<String>::new(), tl.get_descendants(""))
 }
 
+#[test]
+fn get_parents() {
+    let mut tl = TaskList::<&str>::new();
+    let tg = vec![Task::<&str>::new("parent", "world"),
+                  Task::<&str>::new("parent2", "world"),
+                  Task::<&str>::new("child", "world"),
+                  Task::<&str>::new("grandchild", "world")];
+    tl.add_group(tg).ok().unwrap();
+    tl.set_child("parent", "child").ok();
+    tl.set_child("parent2", "child").ok();
+    tl.set_child("child", "grandchild").ok();
+
+    assert_eq!(vec!["parent", "parent2"], tl.get_parents("child"));
+    assert_eq!(vec!["child"], tl.get_parents("grandchild"));
+    assert_eq!(Vec::<String>::new(), tl.get_parents("parent"))
+}
+
 #[test]
 fn is_task_name_present_good() {
     let mut tl = TaskList::<&str>::new();
@@ -15,16 +15,22 @@
 use factotum::tests::make_task;
 use factotum::factfile::*;
 use factotum::executor::*;
+use factotum::executor::execution_strategy::RunResult;
+use factotum::tasklog::AttemptLogs;
+use std::process::Command;
+use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
 
 #[test]
 fn get_task_execution_list_good() {
     let mut ff = Factfile::new("N/A", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     //        apple----------             turnip
     //       /     \         \
@@ -58,12 +64,12 @@ fn get_task_execution_list_good() {
 #[test]
 fn get_task_execution_list_good_reduced() {
     let mut ff = Factfile::new("N/A", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     let tl = get_task_execution_list(&ff, Some("potato".to_string()));
     assert!(tl.tasks[0].len() == 1);
@@ -87,8 +93,8 @@ fn get_task_snapshot_clones() {
     use chrono::duration::Duration;
 
     let mut ff = Factfile::new("N/A", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec!["apple"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec!["apple"])).unwrap();
 
     let mut tl = get_task_execution_list(&ff, None);
 
@@ -102,6 +108,11 @@ fn get_task_snapshot_clones() {
         stdout: Some("hello world".to_string()),
         duration: Duration::seconds(0).to_std().ok().unwrap(),
         task_execution_error: None,
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     });
 
     let snapshot = get_task_snapshot(&tl);
@@ -129,21 +140,24 @@ fn execute_sends_started_msg() {
     use std::time::Duration;
 
     let mut ff = Factfile::new("N/A", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     let task_count_in_factfile = 6;
 
     let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
 
     execute_factfile(&ff,
+                     None,
+                     None,
                      None,
                      execution_strategy::execute_simulation,
-                     Some(tx.clone()));
+                     Some(tx.clone()),
+                     None);
 
     let expected_starting = rx.recv_timeout(Duration::from_millis(300)).unwrap();
 
@@ -175,7 +189,7 @@ fn execute_sends_running_messages() {
 
     for mut task in tasks.into_iter() {
         task.on_result.continue_job.push(0);
-        ff.add_task_obj(&task);
+        ff.add_task_obj(&task).unwrap();
     }
 
     let task_count_in_factfile = 6;
@@ -192,9 +206,12 @@ fn execute_sends_running_messages() {
                                       expected_completed_message_count;
 
     execute_factfile(&ff,
+                     None,
+                     None,
                      None,
                      execution_strategy::execute_simulation,
-                     Some(tx.clone()));
+                     Some(tx.clone()),
+                     None);
 
     println!("Expecting {} messages..", total_expected_task_updates);
 
@@ -259,15 +276,18 @@ fn execute_sends_updates_for_skipped_fail() {
         } else {
             task.on_result.continue_job.push(1);
         }
-        ff.add_task_obj(&task);
+        ff.add_task_obj(&task).unwrap();
     }
 
     let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
 
     execute_factfile(&ff,
+                     None,
+                     None,
                      None,
                      execution_strategy::execute_simulation,
-                     Some(tx.clone()));
+                     Some(tx.clone()),
+                     None);
 
     let mut recv_msg = vec![];
 
@@ -350,15 +370,18 @@ fn execute_sends_failed_skipped_messages() {
 
     for mut task in tasks.into_iter() {
         task.on_result.continue_job.push(1);
-        ff.add_task_obj(&task);
+        ff.add_task_obj(&task).unwrap();
     }
 
     let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
 
     execute_factfile(&ff,
+                     None,
+                     None,
                      None,
                      execution_strategy::execute_simulation,
-                     Some(tx.clone()));
+                     Some(tx.clone()),
+                     None);
 
     let mut recv_msg = vec![];
 
@@ -481,15 +504,18 @@ fn execute_sends_updates_for_skipped_noop() {
         } else {
             task.on_result.terminate_job.push(0);
         }
-        ff.add_task_obj(&task);
+        ff.add_task_obj(&task).unwrap();
     }
 
     let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
 
     execute_factfile(&ff,
+                     None,
+                     None,
                      None,
                      execution_strategy::execute_simulation,
-                     Some(tx.clone()));
+                     Some(tx.clone()),
+                     None);
 
     let mut recv_msg = vec![];
 
@@ -572,15 +598,18 @@ fn execute_sends_noop_skipped_messages() {
 
     for mut task in tasks.into_iter() {
         task.on_result.terminate_job.push(0);
-        ff.add_task_obj(&task);
+        ff.add_task_obj(&task).unwrap();
     }
 
     let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
 
     execute_factfile(&ff,
+                     None,
+                     None,
                      None,
                      execution_strategy::execute_simulation,
-                     Some(tx.clone()));
+                     Some(tx.clone()),
+                     None);
 
     let mut recv_msg = vec![];
 
@@ -711,4 +740,545 @@ fn execute_sends_noop_skipped_messages() {
                Transition::Task(expected_second_task_transition));
 }
 
-// todo write test for rejecting non "shell" execution types
+fn simulate_timeout(name: &str,
+                    command: &mut Command,
+                    timeout: Option<Duration>,
+                    _: Option<&AttemptLogs>)
+                    -> RunResult {
+    let mut result = execution_strategy::execute_simulation(name, command, timeout, None);
+    if name == "apple" {
+        result.timed_out = true;
+        result.return_code = 1;
+    }
+    result
+}
+
+#[test]
+fn execute_fails_timed_out_tasks() {
+    use factotum::executor::task_list::State;
+    use factotum::factfile::Task as FactfileTask;
+
+    let mut ff = Factfile::new("N/A", "test");
+
+    let tasks: Vec<FactfileTask> = vec![make_task("apple", &vec![]),
+                                        make_task("turnip", &vec!["apple"])];
+
+    for mut task in tasks.into_iter() {
+        task.on_result.continue_job.push(0);
+        task.on_result.continue_job.push(1);
+        task.timeout = Some(Duration::from_secs(30));
+        ff.add_task_obj(&task).unwrap();
+    }
+
+    let result = execute_factfile(&ff, None, None, None, simulate_timeout, None, None);
+
+    assert_eq!(result.tasks[0][0].state,
+               State::Failed("the task exceeded its timeout of 30s and was killed".to_string()));
+    assert_eq!(result.tasks[1][0].state,
+               State::Skipped("the task 'apple' failed".to_string()));
+}
+
+fn simulate_signals(name: &str,
+                    command: &mut Command,
+                    timeout: Option<Duration>,
+                    _: Option<&AttemptLogs>)
+                    -> RunResult {
+    use libc;
+    let mut result = execution_strategy::execute_simulation(name, command, timeout, None);
+    match name {
+        "apple" => result.terminating_signal = Some(libc::SIGTERM),
+        "banana" => result.terminating_signal = Some(libc::SIGKILL),
+        _ => (),
+    }
+    if result.terminating_signal.is_some() {
+        result.return_code = 128 + result.terminating_signal.unwrap();
+    }
+    result
+}
+
+#[test]
+fn execute_matches_signals_not_return_codes() {
+    use factotum::executor::task_list::State;
+    use factotum::factfile::Task as FactfileTask;
+    use libc;
+
+    let mut ff = Factfile::new("N/A", "test");
+
+    let tasks: Vec<FactfileTask> = vec![make_task("apple", &vec![]),
+                                        make_task("banana", &vec![]),
+                                        make_task("carrot", &vec!["banana"])];
+
+    for mut task in tasks.into_iter() {
+        task.on_result.continue_job.push(0);
+        task.on_result.continue_job.push(128 + libc::SIGKILL);
+        task.on_result.continue_job_signals.push(libc::SIGTERM);
+        ff.add_task_obj(&task).unwrap();
+    }
+
+    let result = execute_factfile(&ff, None, None, None, simulate_signals, None, None);
+
+    let tasks = result.tasks.iter().flat_map(|tg| tg.iter()).collect::<Vec<_>>();
+    let apple = tasks.iter().find(|t| t.name == "apple").unwrap();
+    assert_eq!(apple.state, State::Success);
+    let banana = tasks.iter().find(|t| t.name == "banana").unwrap();
+    assert_eq!(banana.state,
+               State::Failed("the task was killed by SIGKILL, which isn't specified in onSignal"
+                   .to_string()));
+    let carrot = tasks.iter().find(|t| t.name == "carrot").unwrap();
+    assert_eq!(carrot.state,
+               State::Skipped("the task 'banana' failed".to_string()));
+}
+
+#[test]
+fn execute_applies_task_env_and_working_directory() {
+    use factotum::executor::task_list::State;
+
+    let mut ff = Factfile::new("N/A", "test");
+    let mut task = make_task("apple", &vec![]);
+    task.command = "echo $FRUIT_COLOUR && pwd".to_string();
+    task.on_result.continue_job.push(0);
+    task.env.insert("FRUIT_COLOUR".to_string(), "green".to_string());
+    task.working_directory = Some("/".to_string());
+    ff.add_task_obj(&task).unwrap();
+
+    let result = execute_factfile(&ff,
+                                  None,
+                                  None,
+                                  None,
+                                  execution_strategy::execute_os,
+                                  None,
+                                  None);
+
+    assert_eq!(result.tasks[0][0].state, State::Success);
+    assert_eq!(result.tasks[0][0].run_result.as_ref().unwrap().stdout,
+               Some("green\n/".to_string()));
+}
+
+static FLAKY_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn simulate_flaky(name: &str,
+                  command: &mut Command,
+                  timeout: Option<Duration>,
+                  _: Option<&AttemptLogs>)
+                  -> RunResult {
+    let mut result = execution_strategy::execute_simulation(name, command, timeout, None);
+    if FLAKY_CALLS.fetch_add(1, Ordering::SeqCst) < 2 {
+        result.return_code = 1;
+    }
+    result
+}
+
+#[test]
+fn execute_retries_until_success() {
+    use factotum::executor::task_list::State;
+    use factotum::factfile::Task as FactfileTask;
+
+    let mut ff = Factfile::new("N/A", "test");
+
+    let mut task: FactfileTask = make_task("apple", &vec![]);
+    task.on_result.continue_job.push(0);
+    task.retry = Some(RetryPolicy {
+        max_attempts: 3,
+        delay: Duration::from_secs(0),
+        backoff: Backoff::Fixed,
+        retry_on: vec![1],
+    });
+    ff.add_task_obj(&task).unwrap();
+
+    let result = execute_factfile(&ff, None, None, None, simulate_flaky, None, None);
+
+    assert_eq!(result.tasks[0][0].state, State::Success);
+    let run_result = result.tasks[0][0].run_result.clone().unwrap();
+    assert_eq!(run_result.return_code, 0);
+    assert_eq!(run_result.previous_attempts.len(), 2);
+    assert!(run_result.previous_attempts.iter().all(|a| a.return_code == 1));
+}
+
+#[test]
+fn execute_keeps_the_logs_of_every_attempt() {
+    use factotum::executor::task_list::State;
+    use std::env;
+    use std::fs;
+
+    let mut log_dir = env::temp_dir();
+    log_dir.push("factotum-executor-test-attempt-logs");
+    fs::remove_dir_all(&log_dir).ok();
+    fs::create_dir_all(&log_dir).unwrap();
+
+    let mut ff = Factfile::new("N/A", "test");
+    let mut task = make_task("apple", &vec![]);
+    task.command = "echo attempt; exit 1".to_string();
+    task.on_result.continue_job.push(0);
+    task.retry = Some(RetryPolicy {
+        max_attempts: 2,
+        delay: Duration::from_secs(0),
+        backoff: Backoff::Fixed,
+        retry_on: vec![1],
+    });
+    ff.add_task_obj(&task).unwrap();
+
+    let result = execute_factfile(&ff,
+                                  None,
+                                  None,
+                                  None,
+                                  execution_strategy::execute_os,
+                                  None,
+                                  Some(log_dir.clone()));
+
+    assert!(match result.tasks[0][0].state {
+        State::Failed(_) => true,
+        _ => false,
+    });
+    let run_result = result.tasks[0][0].run_result.clone().unwrap();
+    assert_eq!(run_result.previous_attempts[0].logs.as_ref().unwrap().stdout,
+               log_dir.join("apple.1.out"));
+    assert_eq!(run_result.logs.as_ref().unwrap().stdout,
+               log_dir.join("apple.2.out"));
+    assert!(log_dir.join("apple.1.out").exists());
+    assert!(log_dir.join("apple.2.err").exists());
+
+    fs::remove_dir_all(&log_dir).ok();
+}
+
+static SLOW_TASK_DONE: AtomicBool = AtomicBool::new(false);
+static STARTED_BEFORE_SLOW_TASK_DONE: AtomicBool = AtomicBool::new(false);
+
+fn simulate_slow_apple(name: &str,
+                       command: &mut Command,
+                       timeout: Option<Duration>,
+                       _: Option<&AttemptLogs>)
+                       -> RunResult {
+    match name {
+        "apple" => {
+            thread::sleep(Duration::from_millis(500));
+            SLOW_TASK_DONE.store(true, Ordering::SeqCst);
+        }
+        "carrot" => {
+            STARTED_BEFORE_SLOW_TASK_DONE.store(!SLOW_TASK_DONE.load(Ordering::SeqCst),
+                                                Ordering::SeqCst);
+        }
+        _ => (),
+    }
+    execution_strategy::execute_simulation(name, command, timeout, None)
+}
+
+#[test]
+fn execute_starts_tasks_when_dependencies_succeed() {
+    use factotum::executor::task_list::State;
+    use factotum::factfile::Task as FactfileTask;
+
+    let mut ff = Factfile::new("N/A", "test");
+
+    let tasks: Vec<FactfileTask> = vec![make_task("apple", &vec![]),
+                                        make_task("banana", &vec![]),
+                                        make_task("carrot", &vec!["banana"])];
+
+    for mut task in tasks.into_iter() {
+        task.on_result.continue_job.push(0);
+        ff.add_task_obj(&task).unwrap();
+    }
+
+    let result = execute_factfile(&ff, None, None, None, simulate_slow_apple, None, None);
+
+    // carrot only depends on banana, so it shouldn't wait for apple to finish
+    assert!(STARTED_BEFORE_SLOW_TASK_DONE.load(Ordering::SeqCst));
+    assert!(result.tasks
+        .iter()
+        .flat_map(|tg| tg.iter())
+        .all(|t| t.state == State::Success));
+}
+
+static POOL_RUNNING: AtomicUsize = AtomicUsize::new(0);
+static POOL_MAX_SEEN: AtomicUsize = AtomicUsize::new(0);
+
+fn simulate_pool_usage(name: &str,
+                       command: &mut Command,
+                       timeout: Option<Duration>,
+                       _: Option<&AttemptLogs>)
+                       -> RunResult {
+    let running = POOL_RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    if running > POOL_MAX_SEEN.load(Ordering::SeqCst) {
+        POOL_MAX_SEEN.store(running, Ordering::SeqCst);
+    }
+    thread::sleep(Duration::from_millis(100));
+    POOL_RUNNING.fetch_sub(1, Ordering::SeqCst);
+    execution_strategy::execute_simulation(name, command, timeout, None)
+}
+
+#[test]
+fn execute_respects_pool_limits() {
+    use factotum::executor::task_list::State;
+    use factotum::factfile::Task as FactfileTask;
+
+    let mut ff = Factfile::new("N/A", "test");
+    ff.pools.insert("redshift".to_string(), 2);
+
+    let tasks: Vec<FactfileTask> = vec![make_task("apple", &vec![]),
+                                        make_task("banana", &vec![]),
+                                        make_task("carrot", &vec![]),
+                                        make_task("damson", &vec![]),
+                                        make_task("egg", &vec![])];
+
+    for mut task in tasks.into_iter() {
+        task.on_result.continue_job.push(0);
+        task.pool = Some("redshift".to_string());
+        ff.add_task_obj(&task).unwrap();
+    }
+
+    let result = execute_factfile(&ff, None, None, None, simulate_pool_usage, None, None);
+
+    assert_eq!(POOL_MAX_SEEN.load(Ordering::SeqCst), 2);
+    assert!(result.tasks
+        .iter()
+        .flat_map(|tg| tg.iter())
+        .all(|t| t.state == State::Success));
+}
+
+#[test]
+fn get_ready_tasks_respects_max_parallel() {
+    let mut ff = Factfile::new("N/A", "test");
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("banana", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("carrot", &vec![])).unwrap();
+
+    let tl = get_task_execution_list(&ff, None);
+
+    assert_eq!(get_ready_tasks(&tl, None, &ff.pools).len(), 3);
+    assert_eq!(get_ready_tasks(&tl, Some(2), &ff.pools).len(), 2);
+}
+
+#[test]
+fn execute_skips_tasks_completed_in_previous_run() {
+    use factotum::executor::task_list::State;
+    use factotum::factfile::Task as FactfileTask;
+    use std::collections::HashMap;
+
+    let mut ff = Factfile::new("N/A", "test");
+
+    let tasks: Vec<FactfileTask> = vec![make_task("apple", &vec![]),
+                                        make_task("banana", &vec![]),
+                                        make_task("carrot", &vec!["apple", "banana"]),
+                                        make_task("damson", &vec!["banana"]),
+                                        make_task("egg", &vec!["apple"])];
+
+    for mut task in tasks.into_iter() {
+        task.on_result.continue_job.push(0);
+        ff.add_task_obj(&task).unwrap();
+    }
+
+    let mut completed = HashMap::new();
+    completed.insert("apple".to_string(), State::Success);
+    completed.insert("banana".to_string(), State::SuccessNoop);
+
+    let result = execute_factfile(&ff,
+                                  None,
+                                  None,
+                                  Some(completed),
+                                  execution_strategy::execute_simulation,
+                                  None,
+                                  None);
+
+    let tasks = result.tasks.iter().flat_map(|tg| tg.iter()).collect::<Vec<_>>();
+    let apple = tasks.iter().find(|t| t.name == "apple").unwrap();
+    assert_eq!(apple.state, State::Success);
+    assert!(apple.run_result.is_none());
+    let banana = tasks.iter().find(|t| t.name == "banana").unwrap();
+    assert_eq!(banana.state, State::SuccessNoop);
+    for name in vec!["carrot", "damson"] {
+        assert_eq!(tasks.iter().find(|t| t.name == name).unwrap().state,
+                   State::Skipped("the task 'banana' requested early termination".to_string()));
+    }
+    let egg = tasks.iter().find(|t| t.name == "egg").unwrap();
+    assert_eq!(egg.state, State::Success);
+    assert!(egg.run_result.is_some());
+}
+
+fn find_task(tasklist: &TaskList<&FactfileTask>, name: &str) -> (usize, usize) {
+    for (task_grp_idx, task_group) in tasklist.tasks.iter().enumerate() {
+        if let Some(idx) = task_group.iter().position(|t| t.name == name) {
+            return (task_grp_idx, idx);
+        }
+    }
+    panic!("no task called '{}'", name)
+}
+
+#[test]
+fn cancel_task_skips_descendants() {
+    let mut ff = Factfile::new("N/A", "test");
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("banana", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("carrot", &vec!["apple"])).unwrap();
+
+    let mut tl = get_task_execution_list(&ff, None);
+    let (apple_grp_idx, apple_idx) = find_task(&tl, "apple");
+    tl.tasks[apple_grp_idx][apple_idx].state = State::Running;
+
+    let transitions = cancel_task(&mut tl,
+                                  apple_grp_idx,
+                                  apple_idx,
+                                  "the job was cancelled by SIGTERM".to_string());
+
+    let state_of = |name| {
+        let (grp_idx, idx) = find_task(&tl, name);
+        tl.tasks[grp_idx][idx].state.clone()
+    };
+    assert_eq!(state_of("apple"),
+               State::Cancelled("the job was cancelled by SIGTERM".to_string()));
+    assert_eq!(state_of("banana"), State::Waiting);
+    assert_eq!(state_of("carrot"),
+               State::Skipped("the task 'apple' was cancelled".to_string()));
+    assert_eq!(transitions,
+               vec![TaskTransition::new("carrot",
+                                        State::Waiting,
+                                        State::Skipped("the task 'apple' was cancelled"
+                                            .to_string()))]);
+}
+
+#[test]
+fn skip_waiting_tasks_leaves_finished_tasks_alone() {
+    let mut ff = Factfile::new("N/A", "test");
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("banana", &vec![])).unwrap();
+
+    let mut tl = get_task_execution_list(&ff, None);
+    tl.tasks[0][0].state = State::Success;
+
+    let transitions = skip_waiting_tasks(&mut tl, "the job was cancelled by SIGINT".to_string());
+
+    assert_eq!(tl.tasks[0][0].state, State::Success);
+    assert_eq!(tl.tasks[0][1].state,
+               State::Skipped("the job was cancelled by SIGINT".to_string()));
+    assert_eq!(transitions.len(), 1);
+}
+
+fn simulate_cancellation(name: &str,
+                         command: &mut Command,
+                         timeout: Option<Duration>,
+                         _: Option<&AttemptLogs>)
+                         -> RunResult {
+    use libc;
+    let mut result = execution_strategy::execute_simulation(name, command, timeout, None);
+    match name {
+        "apple" => {
+            // still running when the job is cancelled, so it gets the signal
+            cancellation::sleep_unless_cancelled(Duration::from_secs(10));
+            result.return_code = 128 + libc::SIGTERM;
+        }
+        "banana" => cancellation::request_cancel(libc::SIGTERM),
+        "elderberry" => result.return_code = 1,
+        _ => (),
+    }
+    result
+}
+
+fn run_cancelled_job() {
+    use factotum::executor::task_list::State;
+    use factotum::factfile::Task as FactfileTask;
+    use std::sync::mpsc;
+
+    let mut ff = Factfile::new("N/A", "test");
+
+    let tasks: Vec<FactfileTask> = vec![make_task("apple", &vec![]),
+                                        make_task("banana", &vec![]),
+                                        make_task("carrot", &vec!["banana"]),
+                                        make_task("damson", &vec!["apple"]),
+                                        make_task("elderberry", &vec![])];
+
+    for mut task in tasks.into_iter() {
+        task.on_result.continue_job.push(0);
+        if task.name == "elderberry" {
+            task.retry = Some(RetryPolicy {
+                max_attempts: 3,
+                delay: Duration::from_secs(60),
+                backoff: Backoff::Fixed,
+                retry_on: vec![1],
+            });
+        }
+        ff.add_task_obj(&task).unwrap();
+    }
+
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let result = execute_factfile(&ff, None, None, None, simulate_cancellation, Some(tx), None);
+
+    let state_of = |name| {
+        let (grp_idx, idx) = find_task(&result, name);
+        result.tasks[grp_idx][idx].state.clone()
+    };
+    assert_eq!(state_of("apple"),
+               State::Cancelled("the job was cancelled by SIGTERM".to_string()));
+    assert_eq!(state_of("banana"), State::Success);
+    assert_eq!(state_of("carrot"),
+               State::Skipped("the job was cancelled by SIGTERM".to_string()));
+    assert_eq!(state_of("damson"),
+               State::Skipped("the task 'apple' was cancelled".to_string()));
+    assert_eq!(state_of("elderberry"),
+               State::Cancelled("the job was cancelled by SIGTERM".to_string()));
+
+    // the retry backoff is cut short rather than making another attempt
+    let (grp_idx, idx) = find_task(&result, "elderberry");
+    let elderberry_result = result.tasks[grp_idx][idx].run_result.clone().unwrap();
+    assert_eq!(elderberry_result.return_code, 1);
+    assert!(elderberry_result.previous_attempts.is_empty());
+
+    let updates = rx.try_iter().collect::<Vec<ExecutionUpdate>>();
+    let carrot_skip = TaskTransition::new("carrot",
+                                          State::Waiting,
+                                          State::Skipped("the job was cancelled by SIGTERM"
+                                              .to_string()));
+    let carrot_skipped = updates.iter().any(|u| match u.transition {
+        Transition::Task(ref transitions) => transitions.contains(&carrot_skip),
+        _ => false,
+    });
+    assert!(carrot_skipped);
+    let last_update = updates.last().unwrap();
+    assert_eq!(last_update.execution_state, ExecutionState::Finished);
+    assert_eq!(last_update.transition,
+               Transition::Job(JobTransition::new(Some(ExecutionState::Running),
+                                                  ExecutionState::Finished)));
+}
+
+#[test]
+fn execute_cancelled_job() {
+    use std::env;
+
+    // cancellation is process wide, so the cancelled job runs in a copy of this test binary
+    // where it can't interfere with the other tests
+    if env::var("FACTOTUM_TEST_CANCELLED_JOB").is_ok() {
+        run_cancelled_job();
+        return;
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&["--exact", "factotum::executor::tests::execute_cancelled_job"])
+        .env("FACTOTUM_TEST_CANCELLED_JOB", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("1 passed"), "{}", stdout);
+}
+
+#[test]
+fn exec_tasks_pass_arguments_straight_through() {
+    let mut task = make_task("apple", &vec![]);
+    task.executor = "exec".to_string();
+    task.command = "printf".to_string();
+    task.arguments = vec!["%s|".to_string(), "it's \"$HOME\" `date`".to_string()];
+
+    let result = execution_strategy::execute_os("apple", &mut get_task_command(&task), None, None);
+
+    assert_eq!(result.stdout, Some("it's \"$HOME\" `date`|".to_string()));
+}
+
+#[test]
+fn shell_tasks_run_through_sh() {
+    let mut task = make_task("apple", &vec![]);
+    task.executor = "shell".to_string();
+    task.command = "echo".to_string();
+    task.arguments = vec!["$0".to_string()];
+
+    let result = execution_strategy::execute_os("apple", &mut get_task_command(&task), None, None);
+
+    assert_eq!(result.stdout, Some("sh".to_string()));
+}
@@ -47,12 +47,12 @@ fn generate_graphviz_dot_good() {
     let example = file_contents(&resource("dot/example_apples.dot"));
 
     let mut ff = Factfile::new("N/A", "Sample job");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     print!("EXPECTED:\n{}\n", example);
 
@@ -68,15 +68,15 @@ fn generate_graphviz_dot_good_poly() {
     let example = file_contents(&resource("dot/example_apples_poly.dot"));
 
     let mut ff = Factfile::new("N/A", "Sample job #2");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
-    ff.add_task_obj(&make_task("milk", &vec!["turnip"]));
-    ff.add_task_obj(&make_task("cheese", &vec!["milk"]));
-    ff.add_task_obj(&make_task("cake", &vec!["milk"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
+    ff.add_task_obj(&make_task("milk", &vec!["turnip"])).unwrap();
+    ff.add_task_obj(&make_task("cheese", &vec!["milk"])).unwrap();
+    ff.add_task_obj(&make_task("cake", &vec!["milk"])).unwrap();
 
     print!("EXPECTED:\n{}\n", example);
 
@@ -92,15 +92,15 @@ fn generate_graphviz_dot_good_short() {
     let example = file_contents(&resource("dot/example_reduced.dot"));
 
     let mut ff = Factfile::new("N/A", "Sample job #3 (reduced run)");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
-    ff.add_task_obj(&make_task("milk", &vec!["turnip"]));
-    ff.add_task_obj(&make_task("cheese", &vec!["milk"]));
-    ff.add_task_obj(&make_task("cake", &vec!["milk"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
+    ff.add_task_obj(&make_task("milk", &vec!["turnip"])).unwrap();
+    ff.add_task_obj(&make_task("cheese", &vec!["milk"])).unwrap();
+    ff.add_task_obj(&make_task("cake", &vec!["milk"])).unwrap();
 
     print!("EXPECTED:\n{}\n", example);
 
@@ -18,11 +18,18 @@ mod dot;
 
 use daggy::*;
 use factotum::sequencer;
+use std::time::Duration;
+use std::cmp;
+use std::collections::HashMap;
 
+// shell tasks run their command (and arguments) with sh -c, exec tasks run the command directly
+pub const SHELL_EXECUTOR: &'static str = "shell";
+pub const EXEC_EXECUTOR: &'static str = "exec";
 
 pub struct Factfile {
     pub name: String,
     pub raw: String,
+    pub pools: HashMap<String, usize>,
     dag: Dag<Task, ()>,
     root: NodeIndex,
 }
@@ -35,12 +42,89 @@ pub struct Task {
     pub command: String,
     pub arguments: Vec<String>,
     pub on_result: OnResult,
+    pub timeout: Option<Duration>,
+    pub retry: Option<RetryPolicy>,
+    pub pool: Option<String>,
+    pub env: HashMap<String, String>,
+    pub working_directory: Option<String>,
+}
+
+#[derive(Clone,Debug, PartialEq)]
+pub enum AddTaskError {
+    DuplicateName(String),
+    SelfDependency(String),
+    UndefinedDependency(String, String),
 }
 
 #[derive(Clone,Debug, PartialEq)]
 pub struct OnResult {
     pub terminate_job: Vec<i32>,
     pub continue_job: Vec<i32>,
+    pub terminate_job_signals: Vec<i32>,
+    pub continue_job_signals: Vec<i32>,
+}
+
+impl OnResult {
+    // a task killed by a signal is only matched on the signal, never on its return code
+    // a shell task is rarely killed itself - sh reports the command it ran being killed as
+    // 128 + the signal - so a return code like that is matched on the signal too, unless the
+    // return code is mapped
+    fn matched_signal(&self, return_code: i32, signal: Option<i32>) -> Option<i32> {
+        match signal {
+            Some(s) => Some(s),
+            None if return_code > 128 && !self.terminate_job.contains(&return_code) &&
+                    !self.continue_job.contains(&return_code) => Some(return_code - 128),
+            None => None,
+        }
+    }
+
+    pub fn terminates_job(&self, return_code: i32, signal: Option<i32>) -> bool {
+        match self.matched_signal(return_code, signal) {
+            Some(s) => self.terminate_job_signals.contains(&s),
+            None => self.terminate_job.contains(&return_code),
+        }
+    }
+
+    pub fn continues_job(&self, return_code: i32, signal: Option<i32>) -> bool {
+        match self.matched_signal(return_code, signal) {
+            Some(s) => self.continue_job_signals.contains(&s),
+            None => self.continue_job.contains(&return_code),
+        }
+    }
+}
+
+#[derive(Clone,Debug, PartialEq)]
+pub enum Backoff {
+    Fixed,
+    Exponential,
+}
+
+#[derive(Clone,Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub delay: Duration,
+    pub backoff: Backoff,
+    pub retry_on: Vec<i32>,
+}
+
+impl RetryPolicy {
+    // how long to wait before the given attempt (the first attempt is 1, and isn't delayed)
+    pub fn delay_before_attempt(&self, attempt: u32) -> Duration {
+        if attempt <= 1 {
+            return Duration::from_secs(0);
+        }
+
+        match self.backoff {
+            Backoff::Fixed => self.delay,
+            Backoff::Exponential => {
+                // a long enough delay overflows when doubled, so it's capped rather than panicking
+                let doublings = cmp::min(attempt - 2, 16);
+                self.delay
+                    .checked_mul(2u32.pow(doublings))
+                    .unwrap_or(Duration::from_secs(u64::max_value()))
+            }
+        }
+    }
 }
 
 impl Factfile {
@@ -55,7 +139,14 @@ impl Factfile {
             on_result: OnResult {
                 terminate_job: vec![],
                 continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
             },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
         };
         let parent = new_dag.add_node(root_task);
         Factfile {
@@ -63,6 +154,7 @@ impl Factfile {
             dag: new_dag,
             root: parent,
             raw: raw.into(),
+            pools: HashMap::new(),
         }
     }
 
@@ -111,62 +203,28 @@ impl Factfile {
         }
     }
 
-    // this is used in tests
-    #[cfg(test)]
-    pub fn add_task_obj(&mut self, task: &Task) {
-        self.add_task(&task.name,
-                      &task.depends_on.iter().map(AsRef::as_ref).collect(),
-                      &task.executor,
-                      &task.command,
-                      &task.arguments.iter().map(AsRef::as_ref).collect(),
-                      &task.on_result.terminate_job,
-                      &task.on_result.continue_job) // TODO should this function really be the main one? or even the only one, its nicer to pass a struct as it has named params
-    }
-
-    pub fn add_task(&mut self,
-                    name: &str,
-                    depends_on: &Vec<&str>,
-                    executor: &str,
-                    command: &str,
-                    args: &Vec<&str>,
-                    terminate_job_on: &Vec<i32>,
-                    continue_job_on: &Vec<i32>) {
-        // TODO ensure all fields are validated, Result is returned rather than panic (and get tests in shape for this validation)
-
-        if let Some((_, task)) = self.find_task_by_name(name) {
-            panic!("Key '{}' already exists!", task.name)
+    pub fn add_task_obj(&mut self, task: &Task) -> Result<(), AddTaskError> {
+        if self.find_task_by_name(&task.name).is_some() {
+            return Err(AddTaskError::DuplicateName(task.name.clone()));
         }
 
-        if depends_on.len() > 0 {
-            if depends_on.iter().any(|s| s == &name) {
-                panic!("A task cannot depend on itself");
+        if task.depends_on.len() > 0 {
+            if task.depends_on.iter().any(|s| s == &task.name) {
+                return Err(AddTaskError::SelfDependency(task.name.clone()));
             }
 
             let mut parents: Vec<NodeIndex> = vec![];
-            let mut deps: Vec<String> = vec![];
 
-            for dependency in depends_on {
+            for dependency in task.depends_on.iter() {
                 if let Some((idx, _)) = self.find_task_by_name(dependency) {
                     parents.push(idx);
-                    deps.push(dependency.to_string());
                 } else {
-                    panic!("A task must have it's dependencies already defined - couldn't find \
-                            definition of {}",
-                           dependency);
+                    return Err(AddTaskError::UndefinedDependency(task.name.clone(),
+                                                                 dependency.clone()));
                 }
             }
 
-            let node = self.dag.add_node(Task {
-                name: String::from(name),
-                depends_on: deps,
-                executor: String::from(executor),
-                command: String::from(command),
-                arguments: args.iter().map(|s| String::from(*s)).collect(),
-                on_result: OnResult {
-                    terminate_job: terminate_job_on.iter().map(|i| *i).collect(),
-                    continue_job: continue_job_on.iter().map(|i| *i).collect(),
-                },
-            });
+            let node = self.dag.add_node(task.clone());
 
             for parent in parents {
                 if let Err(_) = self.dag.add_edge(parent, node, ()) {
@@ -176,18 +234,9 @@ impl Factfile {
                 }
             }
         } else {
-            let new_task = Task {
-                name: String::from(name),
-                depends_on: vec![],
-                executor: String::from(executor),
-                command: String::from(command),
-                arguments: args.iter().map(|s| String::from(*s)).collect(),
-                on_result: OnResult {
-                    terminate_job: terminate_job_on.iter().map(|i| *i).collect(),
-                    continue_job: continue_job_on.iter().map(|i| *i).collect(),
-                },
-            };
-            self.dag.add_child(self.root, (), new_task);
+            self.dag.add_child(self.root, (), task.clone());
         }
+
+        Ok(())
     }
 }
@@ -17,38 +17,39 @@ use factotum::tests::compare_tasks;
 use factotum::tests::make_task;
 
 #[test]
-#[should_panic(expected = "Key 'hello' already exists!")]
-fn duplicate_names_panics() {
+fn duplicate_names_err() {
     let mut f = Factfile::new("none", "test");
     let dup_task = make_task("hello", &vec![]);
-    f.add_task_obj(&dup_task);
-    f.add_task_obj(&dup_task);
+    f.add_task_obj(&dup_task).unwrap();
+    assert_eq!(f.add_task_obj(&dup_task),
+               Err(AddTaskError::DuplicateName("hello".to_string())));
 }
 
 #[test]
-#[should_panic(expected = "A task cannot depend on itself")]
-fn job_depend_itself() {
+fn job_depend_itself_err() {
     let self_depending_task = make_task("hello", &vec!["hello"]);
-    Factfile::new("none", "test").add_task_obj(&self_depending_task);
+    assert_eq!(Factfile::new("none", "test").add_task_obj(&self_depending_task),
+               Err(AddTaskError::SelfDependency("hello".to_string())));
 }
 
 #[test]
-#[should_panic(expected = "A task must have it's dependencies already defined")]
-fn task_depend_existing_tasks_only() {
+fn task_depend_existing_tasks_only_err() {
     let non_existing_task = make_task("mytask", &vec!["undefined as yet"]);
     let mut ff = Factfile::new("none", "test");
-    ff.add_task_obj(&non_existing_task);
+    assert_eq!(ff.add_task_obj(&non_existing_task),
+               Err(AddTaskError::UndefinedDependency("mytask".to_string(),
+                                                     "undefined as yet".to_string())));
 }
 
 #[test]
 fn a_complicated_tree_works() {
     let mut ff = Factfile::new("none", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     //           FactotumJob --------------
     //          /                          \
@@ -71,10 +72,10 @@ fn a_complicated_tree_works() {
 #[test]
 fn heirachy_only_last() {
     let mut ff = Factfile::new("none", "test");
-    ff.add_task_obj(&make_task("a", &vec![]));
-    ff.add_task_obj(&make_task("b", &vec!["a"]));
-    ff.add_task_obj(&make_task("c", &vec!["a"]));
-    ff.add_task_obj(&make_task("banana", &vec!["b", "a"]));
+    ff.add_task_obj(&make_task("a", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("b", &vec!["a"])).unwrap();
+    ff.add_task_obj(&make_task("c", &vec!["a"])).unwrap();
+    ff.add_task_obj(&make_task("banana", &vec!["b", "a"])).unwrap();
 
     //              FactotumJob
     //                  |
@@ -99,10 +100,10 @@ fn heirachy_only_last() {
 #[test]
 fn no_cycles_ok() {
     let mut ff = Factfile::new("none", "test");
-    ff.add_task_obj(&make_task("hello", &vec![]));
-    ff.add_task_obj(&make_task("hello_world", &vec!["hello"]));
-    ff.add_task_obj(&make_task("say_hello", &vec!["hello"]));
-    ff.add_task_obj(&make_task("say_goodbye", &vec!["say_hello", "hello_world"]));
+    ff.add_task_obj(&make_task("hello", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("hello_world", &vec!["hello"])).unwrap();
+    ff.add_task_obj(&make_task("say_hello", &vec!["hello"])).unwrap();
+    ff.add_task_obj(&make_task("say_goodbye", &vec!["say_hello", "hello_world"])).unwrap();
 
     //              FactotumJob
     //                  |
@@ -123,9 +124,9 @@ fn no_cycles_ok() {
 fn can_job_run_from_task_name_not_present_in_dag() {
     // invalid task name
     let mut ff = Factfile::new("none", "test");
-    ff.add_task_obj(&make_task("a", &vec![]));
-    ff.add_task_obj(&make_task("b", &vec!["a"]));
-    ff.add_task_obj(&make_task("c", &vec!["a"]));
+    ff.add_task_obj(&make_task("a", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("b", &vec!["a"])).unwrap();
+    ff.add_task_obj(&make_task("c", &vec!["a"])).unwrap();
 
     match ff.can_job_run_from_task("this task name does not exist") {
         Err(msg) => assert_eq!(msg, "the task specified could not be found"),
@@ -138,10 +139,10 @@ fn can_job_run_from_task_name_not_present_in_dag() {
 fn can_job_run_from_task_name() {
     let mut ff = Factfile::new("none", "test");
 
-    ff.add_task_obj(&make_task("a", &vec![]));
-    ff.add_task_obj(&make_task("b", &vec!["a"]));
-    ff.add_task_obj(&make_task("c", &vec!["a"]));
-    ff.add_task_obj(&make_task("d", &vec!["b", "c"]));
+    ff.add_task_obj(&make_task("a", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("b", &vec!["a"])).unwrap();
+    ff.add_task_obj(&make_task("c", &vec!["a"])).unwrap();
+    ff.add_task_obj(&make_task("d", &vec!["b", "c"])).unwrap();
 
     match ff.can_job_run_from_task("c") { // we can't run from c, because d depends on b also
         Ok(b) => assert_eq!(false, b),
@@ -166,9 +167,9 @@ fn running_jobs_from_non_existing_task() {
 #[test]
 fn running_jobs_from_existing_task() {
     let mut ff = Factfile::new("none", "test");
-    ff.add_task_obj(&make_task("a", &vec![]));
-    ff.add_task_obj(&make_task("b", &vec!["a"]));
-    ff.add_task_obj(&make_task("c", &vec!["b"]));
+    ff.add_task_obj(&make_task("a", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("b", &vec!["a"])).unwrap();
+    ff.add_task_obj(&make_task("c", &vec!["b"])).unwrap();
 
     let expected = vec![vec!["b"], vec!["c"]];
 
@@ -176,3 +177,76 @@ fn running_jobs_from_existing_task() {
 
     compare_tasks(expected, actual);
 }
+
+#[test]
+fn retry_delays_fixed() {
+    use std::time::Duration;
+
+    let policy = RetryPolicy {
+        max_attempts: 4,
+        delay: Duration::from_secs(10),
+        backoff: Backoff::Fixed,
+        retry_on: vec![1],
+    };
+
+    assert_eq!(policy.delay_before_attempt(1), Duration::from_secs(0));
+    assert_eq!(policy.delay_before_attempt(2), Duration::from_secs(10));
+    assert_eq!(policy.delay_before_attempt(4), Duration::from_secs(10));
+}
+
+#[test]
+fn retry_delays_exponential() {
+    use std::time::Duration;
+
+    let policy = RetryPolicy {
+        max_attempts: 4,
+        delay: Duration::from_secs(10),
+        backoff: Backoff::Exponential,
+        retry_on: vec![1],
+    };
+
+    assert_eq!(policy.delay_before_attempt(1), Duration::from_secs(0));
+    assert_eq!(policy.delay_before_attempt(2), Duration::from_secs(10));
+    assert_eq!(policy.delay_before_attempt(3), Duration::from_secs(20));
+    assert_eq!(policy.delay_before_attempt(4), Duration::from_secs(40));
+}
+
+#[test]
+fn retry_delays_exponential_capped() {
+    use std::time::Duration;
+
+    let policy = RetryPolicy {
+        max_attempts: 20,
+        delay: Duration::from_secs(u64::max_value() / 4),
+        backoff: Backoff::Exponential,
+        retry_on: vec![1],
+    };
+
+    assert_eq!(policy.delay_before_attempt(3),
+               Duration::from_secs(u64::max_value() / 4 * 2));
+    assert_eq!(policy.delay_before_attempt(18),
+               Duration::from_secs(u64::max_value()));
+}
+
+#[test]
+fn on_result_matches_signals() {
+    let on_result = OnResult {
+        terminate_job: vec![],
+        continue_job: vec![0, 143],
+        terminate_job_signals: vec![9],
+        continue_job_signals: vec![15],
+    };
+
+    // killed by the signal
+    assert!(on_result.continues_job(143, Some(15)));
+    assert!(on_result.terminates_job(137, Some(9)));
+    assert!(!on_result.continues_job(0, Some(2)));
+
+    // a shell reporting a command was killed, unless the return code is mapped itself
+    assert!(on_result.terminates_job(137, None));
+    assert!(!on_result.continues_job(137, None));
+    assert!(on_result.continues_job(143, None));
+    assert!(!on_result.terminates_job(143, None));
+    assert!(!on_result.continues_job(130, None));
+    assert!(on_result.continues_job(0, None));
+}
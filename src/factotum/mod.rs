@@ -17,6 +17,10 @@ pub mod parser;
 pub mod executor;
 pub mod sequencer;
 pub mod webhook;
+pub mod runstate;
+pub mod report;
+pub mod tasklog;
+pub mod cancellation;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,151 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+extern crate yaml_rust;
+extern crate toml;
+
+#[cfg(test)]
+mod tests;
+
+use rustc_serialize::json::{Json, Object};
+use self::yaml_rust::{Yaml, YamlLoader};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Format, String> {
+        match name.to_lowercase().as_ref() {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            _ => {
+                Err(format!("unknown factfile format '{}' (expected one of json, yaml or toml)",
+                            name))
+            }
+        }
+    }
+
+    pub fn from_filename(filename: &str) -> Format {
+        // anything that isn't obviously yaml or toml is treated as json, like before
+        Path::new(filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| Format::from_name(ext).ok())
+            .unwrap_or(Format::Json)
+    }
+}
+
+pub fn to_json_str(contents: &str, format: Format) -> Result<String, String> {
+    match format {
+        Format::Json => Ok(contents.to_string()),
+        Format::Yaml => yaml_to_json(contents).map(|j| j.to_string()),
+        Format::Toml => toml_to_json(contents).map(|j| j.to_string()),
+    }
+}
+
+pub fn yaml_to_json(contents: &str) -> Result<Json, String> {
+    let docs = try!(YamlLoader::load_from_str(contents)
+        .map_err(|e| format!("the factfile isn't valid YAML: {:?}", e)));
+    match docs.len() {
+        1 => yaml_value_to_json(&docs[0]),
+        0 => Err("the factfile doesn't contain a YAML document".to_string()),
+        _ => Err("the factfile contains more than one YAML document".to_string()),
+    }
+}
+
+// matches Json::from_str, which reads non-negative integers as U64
+fn int_to_json(i: i64) -> Json {
+    if i >= 0 {
+        Json::U64(i as u64)
+    } else {
+        Json::I64(i)
+    }
+}
+
+fn yaml_value_to_json(value: &Yaml) -> Result<Json, String> {
+    match *value {
+        Yaml::Null => Ok(Json::Null),
+        Yaml::Boolean(b) => Ok(Json::Boolean(b)),
+        Yaml::Integer(i) => Ok(int_to_json(i)),
+        Yaml::Real(ref r) => {
+            r.parse::<f64>()
+                .map(Json::F64)
+                .map_err(|_| format!("'{}' is not a valid number", r))
+        }
+        Yaml::String(ref s) => Ok(Json::String(s.clone())),
+        Yaml::Array(ref items) => {
+            let mut json_items = vec![];
+            for item in items.iter() {
+                json_items.push(try!(yaml_value_to_json(item)));
+            }
+            Ok(Json::Array(json_items))
+        }
+        Yaml::Hash(ref hash) => {
+            let mut obj = Object::new();
+            for (key, val) in hash.iter() {
+                let key_str = match *key {
+                    Yaml::String(ref s) => s.clone(),
+                    Yaml::Integer(i) => i.to_string(),
+                    Yaml::Boolean(b) => b.to_string(),
+                    _ => return Err(format!("unsupported YAML key {:?}", key)),
+                };
+                obj.insert(key_str, try!(yaml_value_to_json(val)));
+            }
+            Ok(Json::Object(obj))
+        }
+        Yaml::Alias(_) | Yaml::BadValue => Err(format!("unsupported YAML value {:?}", value)),
+    }
+}
+
+pub fn toml_to_json(contents: &str) -> Result<Json, String> {
+    let mut parser = toml::Parser::new(contents);
+    match parser.parse() {
+        Some(table) => Ok(toml_value_to_json(&toml::Value::Table(table))),
+        None => {
+            let errors = parser.errors
+                .iter()
+                .map(|e| {
+                    let (line, col) = parser.to_linecol(e.lo);
+                    format!("{} at line {}, column {}", e.desc, line + 1, col + 1)
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            Err(format!("the factfile isn't valid TOML: {}", errors))
+        }
+    }
+}
+
+fn toml_value_to_json(value: &toml::Value) -> Json {
+    match *value {
+        toml::Value::String(ref s) => Json::String(s.clone()),
+        toml::Value::Integer(i) => int_to_json(i),
+        toml::Value::Float(f) => Json::F64(f),
+        toml::Value::Boolean(b) => Json::Boolean(b),
+        toml::Value::Datetime(ref d) => Json::String(d.clone()),
+        toml::Value::Array(ref items) => {
+            Json::Array(items.iter().map(toml_value_to_json).collect())
+        }
+        toml::Value::Table(ref table) => {
+            Json::Object(table.iter()
+                .map(|(k, v)| (k.clone(), toml_value_to_json(v)))
+                .collect())
+        }
+    }
+}
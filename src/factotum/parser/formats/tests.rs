@@ -0,0 +1,79 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use factotum::parser::formats::*;
+use rustc_serialize::json::Json;
+
+fn from_json(json: &str) -> Json {
+    Json::from_str(json).unwrap()
+}
+
+#[test]
+fn format_from_filename() {
+    assert_eq!(Format::from_filename("job.yaml"), Format::Yaml);
+    assert_eq!(Format::from_filename("job.YML"), Format::Yaml);
+    assert_eq!(Format::from_filename("dir/job.toml"), Format::Toml);
+    assert_eq!(Format::from_filename("job.factfile"), Format::Json);
+    assert_eq!(Format::from_filename("job"), Format::Json);
+}
+
+#[test]
+fn format_from_name() {
+    assert_eq!(Format::from_name("json"), Ok(Format::Json));
+    assert_eq!(Format::from_name("YAML"), Ok(Format::Yaml));
+    assert_eq!(Format::from_name("toml"), Ok(Format::Toml));
+    assert_eq!(Format::from_name("xml"),
+               Err("unknown factfile format 'xml' (expected one of json, yaml or toml)"
+                   .to_string()));
+}
+
+#[test]
+fn yaml_converted() {
+    let yaml = "# a comment\nname: hello\ncodes: [ 0, 3 ]\nnested:\n  ratio: 0.5\n  ok: true\n";
+    assert_eq!(yaml_to_json(yaml).unwrap(),
+               from_json("{\"name\":\"hello\",\"codes\":[0,3],\"nested\":{\"ratio\":0.5,\
+                          \"ok\":true}}"));
+}
+
+#[test]
+fn yaml_negative_integer_converted() {
+    assert_eq!(yaml_to_json("codes: [ -1, 1 ]\n").unwrap(),
+               from_json("{\"codes\":[-1,1]}"));
+}
+
+#[test]
+fn yaml_invalid_err() {
+    assert!(yaml_to_json("name: [hello").is_err());
+    assert!(yaml_to_json("").is_err());
+}
+
+#[test]
+fn toml_converted() {
+    let toml = "# a comment\nname = \"hello\"\ncodes = [ 0, 3 ]\n\n[nested]\nratio = 0.5\nok = \
+                true\n";
+    assert_eq!(toml_to_json(toml).unwrap(),
+               from_json("{\"name\":\"hello\",\"codes\":[0,3],\"nested\":{\"ratio\":0.5,\
+                          \"ok\":true}}"));
+}
+
+#[test]
+fn toml_negative_integer_converted() {
+    assert_eq!(toml_to_json("codes = [ -1, 1 ]\n").unwrap(),
+               from_json("{\"codes\":[-1,1]}"));
+}
+
+#[test]
+fn toml_invalid_err() {
+    assert!(toml_to_json("name = ").is_err());
+}
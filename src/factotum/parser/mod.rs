@@ -16,13 +16,21 @@
 mod tests;
 mod templater;
 pub mod schemavalidator;
+pub mod formats;
 
 use std::io::prelude::*;
 use std::fs::File;
+use std::time::Duration;
+use std::collections::{BTreeMap, HashMap};
 use rustc_serialize::json::{self, Json};
+use rustc_serialize::{Encodable, Encoder};
 use super::factfile;
+use super::sequencer;
+use super::cancellation;
+use self::formats::Format;
 
 use std::error::Error;
+use std::fmt;
 
 pub struct TaskReturnCodeMapping {
     pub continue_job: Vec<i32>,
@@ -34,25 +42,124 @@ pub enum OverrideResultMappings {
     None,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Read(String),
+    InvalidFactfile(String, String),
+    DuplicateTaskName(String, String),
+    SelfDependency(String, String),
+    UndefinedDependency(String, String, String),
+    DependencyCycle(String, Vec<String>),
+}
+
+impl ParseError {
+    fn from_add_task_error(factfile: &str, err: factfile::AddTaskError) -> ParseError {
+        let factfile = factfile.to_string();
+        match err {
+            factfile::AddTaskError::DuplicateName(task) => {
+                ParseError::DuplicateTaskName(factfile, task)
+            }
+            factfile::AddTaskError::SelfDependency(task) => {
+                ParseError::SelfDependency(factfile, task)
+            }
+            factfile::AddTaskError::UndefinedDependency(task, dependency) => {
+                ParseError::UndefinedDependency(factfile, task, dependency)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Read(ref msg) => write!(f, "{}", msg),
+            ParseError::InvalidFactfile(ref factfile, ref reason) => {
+                write!(f, "'{}' is not a valid factotum factfile: {}", factfile, reason)
+            }
+            ParseError::DuplicateTaskName(ref factfile, ref task) => {
+                write!(f,
+                       "'{}' is not a valid factotum factfile: the task name '{}' is used more \
+                        than once - task names must be unique.",
+                       factfile,
+                       task)
+            }
+            ParseError::SelfDependency(ref factfile, ref task) => {
+                write!(f,
+                       "'{}' is not a valid factotum factfile: the task '{}' lists itself in \
+                        dependsOn.",
+                       factfile,
+                       task)
+            }
+            ParseError::UndefinedDependency(ref factfile, ref task, ref dependency) => {
+                write!(f,
+                       "'{}' is not a valid factotum factfile: the task '{}' has '{}' in \
+                        dependsOn, but no task with that name is defined.",
+                       factfile,
+                       task,
+                       dependency)
+            }
+            ParseError::DependencyCycle(ref factfile, ref cycle) => {
+                write!(f,
+                       "'{}' is not a valid factotum factfile: the tasks' dependsOn form a \
+                        cycle - {}.",
+                       factfile,
+                       cycle.iter()
+                           .map(|name| format!("'{}'", name))
+                           .collect::<Vec<String>>()
+                           .join(" -> "))
+            }
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn description(&self) -> &str {
+        match *self {
+            ParseError::Read(_) => "the factfile couldn't be read",
+            ParseError::InvalidFactfile(_, _) => "the factfile is invalid",
+            ParseError::DuplicateTaskName(_, _) => "a task name is used more than once",
+            ParseError::SelfDependency(_, _) => "a task depends on itself",
+            ParseError::UndefinedDependency(_, _, _) => "a task depends on an undefined task",
+            ParseError::DependencyCycle(_, _) => "the tasks' dependencies form a cycle",
+        }
+    }
+}
+
+#[allow(dead_code)]
 pub fn parse(factfile: &str,
              env: Option<Json>,
              overrides: OverrideResultMappings)
-             -> Result<factfile::Factfile, String> {
+             -> Result<factfile::Factfile, ParseError> {
+    parse_with_format(factfile, None, env, overrides)
+}
+
+pub fn parse_with_format(factfile: &str,
+                         format: Option<Format>,
+                         env: Option<Json>,
+                         overrides: OverrideResultMappings)
+                         -> Result<factfile::Factfile, ParseError> {
     info!("reading {} into memory", factfile);
-    let mut fh = try!(File::open(&factfile)
-        .map_err(|e| format!("Couldn't open '{}' for reading: {}", factfile, e)));
+    let mut fh = try!(File::open(&factfile).map_err(|e| {
+        ParseError::Read(format!("Couldn't open '{}' for reading: {}", factfile, e))
+    }));
     let mut f = String::new();
-    try!(fh.read_to_string(&mut f).map_err(|e| format!("Couldn't read '{}': {}", factfile, e)));
+    try!(fh.read_to_string(&mut f)
+        .map_err(|e| ParseError::Read(format!("Couldn't read '{}': {}", factfile, e))));
     info!("file {} was read successfully!", factfile);
 
-    parse_str(&f, factfile, env, overrides)
+    let format = format.unwrap_or(Format::from_filename(factfile));
+    info!("treating {} as {:?}", factfile, format);
+    let json = try!(formats::to_json_str(&f, format)
+        .map_err(|msg| ParseError::InvalidFactfile(factfile.to_string(), msg)));
+
+    parse_str(&json, factfile, env, overrides)
 }
 
-fn parse_str(json: &str,
-             from_filename: &str,
-             env: Option<Json>,
-             overrides: OverrideResultMappings)
-             -> Result<factfile::Factfile, String> {
+pub fn parse_str(json: &str,
+                 from_filename: &str,
+                 env: Option<Json>,
+                 overrides: OverrideResultMappings)
+                 -> Result<factfile::Factfile, ParseError> {
     info!("parsing json:\n{}", json);
 
     let validation_result = schemavalidator::validate_against_factfile_schema(json);
@@ -62,18 +169,12 @@ fn parse_str(json: &str,
             info!("'{}' matches the factotum schema definition!",
                   from_filename);
 
-            parse_valid_json(json, env, overrides).map_err(|msg| {
-                format!("'{}' is not a valid factotum factfile: {}",
-                        from_filename,
-                        msg)
-            })
+            parse_valid_json(json, from_filename, env, overrides)
         }
         Err(msg) => {
             info!("'{}' failed to match factfile schema definition!",
                   from_filename);
-            Err(format!("'{}' is not a valid factotum factfile: {}",
-                        from_filename,
-                        msg))
+            Err(ParseError::InvalidFactfile(from_filename.to_string(), msg))
         }
     }
 }
@@ -86,13 +187,27 @@ struct SelfDescribingJson {
     data: FactfileFormat,
 }
 
-#[derive(RustcEncodable, RustcDecodable)]
+#[derive(RustcDecodable)]
 struct FactfileFormat {
     name: String,
     tasks: Vec<FactfileTaskFormat>,
+    pools: Option<BTreeMap<String, usize>>,
 }
 
-#[derive(RustcEncodable, RustcDecodable)]
+impl Encodable for FactfileFormat {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("FactfileFormat", 3, |s| {
+            try!(s.emit_struct_field("name", 0, |s| self.name.encode(s)));
+            try!(s.emit_struct_field("tasks", 1, |s| self.tasks.encode(s)));
+            if let Some(ref pools) = self.pools {
+                try!(s.emit_struct_field("pools", 2, |s| pools.encode(s)));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(RustcDecodable)]
 #[allow(non_snake_case)]
 struct FactfileTaskFormat {
     name: String,
@@ -101,61 +216,182 @@ struct FactfileTaskFormat {
     arguments: Vec<String>,
     dependsOn: Vec<String>,
     onResult: FactfileTaskResultFormat,
+    timeoutSeconds: Option<u64>,
+    retry: Option<FactfileTaskRetryFormat>,
+    pool: Option<String>,
+    env: Option<BTreeMap<String, String>>,
+    workingDirectory: Option<String>,
 }
 
-#[derive(RustcEncodable, RustcDecodable, Clone)]
+impl Encodable for FactfileTaskFormat {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        // optional fields are only emitted when set, so the compacted factfile (and the job
+        // reference derived from it) is unchanged for factfiles that don't use them
+        s.emit_struct("FactfileTaskFormat", 11, |s| {
+            try!(s.emit_struct_field("name", 0, |s| self.name.encode(s)));
+            try!(s.emit_struct_field("executor", 1, |s| self.executor.encode(s)));
+            try!(s.emit_struct_field("command", 2, |s| self.command.encode(s)));
+            try!(s.emit_struct_field("arguments", 3, |s| self.arguments.encode(s)));
+            try!(s.emit_struct_field("dependsOn", 4, |s| self.dependsOn.encode(s)));
+            try!(s.emit_struct_field("onResult", 5, |s| self.onResult.encode(s)));
+            if let Some(ref timeout) = self.timeoutSeconds {
+                try!(s.emit_struct_field("timeoutSeconds", 6, |s| timeout.encode(s)));
+            }
+            if let Some(ref retry) = self.retry {
+                try!(s.emit_struct_field("retry", 7, |s| retry.encode(s)));
+            }
+            if let Some(ref pool) = self.pool {
+                try!(s.emit_struct_field("pool", 8, |s| pool.encode(s)));
+            }
+            if let Some(ref env) = self.env {
+                try!(s.emit_struct_field("env", 9, |s| env.encode(s)));
+            }
+            if let Some(ref working_dir) = self.workingDirectory {
+                try!(s.emit_struct_field("workingDirectory", 10, |s| working_dir.encode(s)));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(RustcDecodable, Clone)]
 #[allow(non_snake_case)]
 struct FactfileTaskResultFormat {
     terminateJobWithSuccess: Vec<i32>,
     continueJob: Vec<i32>,
+    onSignal: Option<BTreeMap<String, String>>,
+}
+
+impl Encodable for FactfileTaskResultFormat {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("FactfileTaskResultFormat", 3, |s| {
+            try!(s.emit_struct_field("terminateJobWithSuccess",
+                                     0,
+                                     |s| self.terminateJobWithSuccess.encode(s)));
+            try!(s.emit_struct_field("continueJob", 1, |s| self.continueJob.encode(s)));
+            if let Some(ref on_signal) = self.onSignal {
+                try!(s.emit_struct_field("onSignal", 2, |s| on_signal.encode(s)));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(RustcEncodable, RustcDecodable, Clone)]
+#[allow(non_snake_case)]
+struct FactfileTaskRetryFormat {
+    maxAttempts: u32,
+    delaySeconds: u64,
+    backoff: String,
+    retryOn: Vec<i32>,
 }
 
 fn parse_valid_json(file: &str,
+                    from_filename: &str,
                     conf: Option<Json>,
                     overrides: OverrideResultMappings)
-                    -> Result<factfile::Factfile, String> {
-    let schema: SelfDescribingJson = try!(json::decode(file).map_err(|e| e.to_string()));
-    let compact_json:String = try!(json::encode(&schema).map_err(|e| e.to_string()));
+                    -> Result<factfile::Factfile, ParseError> {
+    let invalid = |msg: String| ParseError::InvalidFactfile(from_filename.to_string(), msg);
+
+    let schema: SelfDescribingJson = try!(json::decode(file).map_err(|e| invalid(e.to_string())));
+    let compact_json:String = try!(json::encode(&schema).map_err(|e| invalid(e.to_string())));
     let decoded_json = schema.data;
 
     let final_compact_json:String = if let Some(ref subs) = conf {
-        try!(templater::decorate_str(&compact_json, &subs))
+        try!(templater::decorate_str(&compact_json, &subs).map_err(&invalid))
     } else {
         compact_json.clone()
     }.to_string();
 
     let final_dag_name = if let Some(ref subs) = conf {
-        try!(templater::decorate_str(&decoded_json.name, &subs))
+        try!(templater::decorate_str(&decoded_json.name, &subs).map_err(&invalid))
     } else {
         decoded_json.name.clone()
     }.to_string();
 
     let mut ff = factfile::Factfile::new(final_compact_json, final_dag_name);
 
+    if let Some(ref pools) = decoded_json.pools {
+        ff.pools = pools.iter().map(|(name, size)| (name.clone(), *size)).collect();
+    }
+
+    let mut tasks = vec![];
+
     for file_task in decoded_json.tasks.iter() {
         let final_name = if let Some(ref subs) = conf {
-            try!(templater::decorate_str(&file_task.name, &subs))
+            try!(templater::decorate_str(&file_task.name, &subs).map_err(&invalid))
         } else {
             file_task.name.clone()
         }.to_string();
 
-        // TODO errs in here - ? add task should Result not panic!
         info!("adding task '{}'", final_name);
 
         if file_task.onResult.continueJob.len() == 0 {
-            return Err(format!("the task '{}' has no way to continue successfully.",
-                               final_name));
+            return Err(invalid(format!("the task '{}' has no way to continue successfully.",
+                                       final_name)));
         } else {
             for cont in file_task.onResult.continueJob.iter() {
                 if file_task.onResult
                     .terminateJobWithSuccess
                     .iter()
                     .any(|conflict| conflict == cont) {
-                    return Err(format!("the task '{}' has conflicting actions.", final_name));
+                    return Err(invalid(format!("the task '{}' has conflicting actions.",
+                                               final_name)));
                 }
             }
         }
 
+        if let Some(ref retry) = file_task.retry {
+            if retry.retryOn.iter().any(|code| {
+                file_task.onResult.continueJob.contains(code) ||
+                file_task.onResult.terminateJobWithSuccess.contains(code)
+            }) {
+                return Err(invalid(format!("the task '{}' retries on a return code that also \
+                                            continues or terminates the job.",
+                                           final_name)));
+            }
+        }
+
+        let mut terminate_signals = vec![];
+        let mut continue_signals = vec![];
+        if let Some(ref on_signal) = file_task.onResult.onSignal {
+            for (name, action) in on_signal.iter() {
+                let signal = match cancellation::signal_number(name) {
+                    Some(signal) => signal,
+                    None => {
+                        return Err(invalid(format!("the task '{}' has an unknown signal '{}' \
+                                                    in onSignal.",
+                                                   final_name,
+                                                   name)))
+                    }
+                };
+                if action == "continue" {
+                    continue_signals.push(signal);
+                } else {
+                    terminate_signals.push(signal);
+                }
+            }
+        }
+
+        if file_task.executor != factfile::SHELL_EXECUTOR &&
+           file_task.executor != factfile::EXEC_EXECUTOR {
+            return Err(invalid(format!("the task '{}' uses the executor '{}', which isn't \
+                                        supported (the executor must be '{}' or '{}').",
+                                       final_name,
+                                       file_task.executor,
+                                       factfile::SHELL_EXECUTOR,
+                                       factfile::EXEC_EXECUTOR)));
+        }
+
+        if let Some(ref pool) = file_task.pool {
+            if !ff.pools.contains_key(pool) {
+                return Err(invalid(format!("the task '{}' uses the pool '{}', which isn't \
+                                            defined in the factfile's pools.",
+                                           final_name,
+                                           pool)));
+            }
+        }
+
         let mut decorated_args = vec![];
         let mut decorated_deps = vec![];
         if let Some(ref subs) = conf {
@@ -166,10 +402,11 @@ fn parse_valid_json(file: &str,
                   file_task.command,
                   file_task.arguments.join(" "));
 
-            let decorated_command = try!(templater::decorate_str(&file_task.command, &subs));
+            let decorated_command = try!(templater::decorate_str(&file_task.command, &subs)
+                .map_err(&invalid));
 
             for arg in file_task.arguments.iter() {
-                decorated_args.push(try!(templater::decorate_str(arg, &subs)))
+                decorated_args.push(try!(templater::decorate_str(arg, &subs).map_err(&invalid)))
             }
 
             info!("after:\n\tcommand: '{}'\n\targs: '{}'",
@@ -177,7 +414,7 @@ fn parse_valid_json(file: &str,
                   decorated_args.join(" "));
 
             for dep in file_task.dependsOn.iter() {
-                decorated_deps.push(try!(templater::decorate_str(dep, &subs)))
+                decorated_deps.push(try!(templater::decorate_str(dep, &subs).map_err(&invalid)))
             }
 
             info!("after:\n\tcommand: '{}'\n\tdeps: '{}'",
@@ -193,8 +430,29 @@ fn parse_valid_json(file: &str,
             }
         }
 
-        let deps: Vec<&str> = decorated_deps.iter().map(AsRef::as_ref).collect();
-        let args: Vec<&str> = decorated_args.iter().map(AsRef::as_ref).collect();
+        // env values and the working directory can use variables too (env names can't)
+        let mut decorated_env = HashMap::new();
+        if let Some(ref env) = file_task.env {
+            for (key, value) in env.iter() {
+                let decorated_value = if let Some(ref subs) = conf {
+                    try!(templater::decorate_str(value, &subs).map_err(&invalid))
+                } else {
+                    value.clone()
+                };
+                decorated_env.insert(key.clone(), decorated_value);
+            }
+        }
+
+        let decorated_working_dir = match file_task.workingDirectory {
+            Some(ref dir) => {
+                if let Some(ref subs) = conf {
+                    Some(try!(templater::decorate_str(dir, &subs).map_err(&invalid)))
+                } else {
+                    Some(dir.clone())
+                }
+            }
+            None => None,
+        };
 
         let (terminate_mappings, continue_mappings) = match overrides {
             OverrideResultMappings::All(ref with_value) => {
@@ -205,13 +463,45 @@ fn parse_valid_json(file: &str,
             }
         };
 
-        ff.add_task(&final_name,
-                    &deps,
-                    &file_task.executor,
-                    &file_task.command,
-                    &args,
-                    terminate_mappings,
-                    continue_mappings);
+        tasks.push(factfile::Task {
+            name: final_name,
+            depends_on: decorated_deps,
+            executor: file_task.executor.clone(),
+            command: file_task.command.clone(),
+            arguments: decorated_args,
+            on_result: factfile::OnResult {
+                terminate_job: terminate_mappings.clone(),
+                continue_job: continue_mappings.clone(),
+                terminate_job_signals: terminate_signals,
+                continue_job_signals: continue_signals,
+            },
+            timeout: file_task.timeoutSeconds.map(Duration::from_secs),
+            retry: file_task.retry.as_ref().map(|r| {
+                factfile::RetryPolicy {
+                    max_attempts: r.maxAttempts,
+                    delay: Duration::from_secs(r.delaySeconds),
+                    backoff: if r.backoff == "exponential" {
+                        factfile::Backoff::Exponential
+                    } else {
+                        factfile::Backoff::Fixed
+                    },
+                    retry_on: r.retryOn.clone(),
+                }
+            }),
+            pool: file_task.pool.clone(),
+            env: decorated_env,
+            working_directory: decorated_working_dir,
+        });
     }
+
+    // tasks can be declared in any order, so they're only added once their parents are known
+    let sorted_tasks = try!(sequencer::sort_by_dependencies(tasks)
+        .map_err(|cycle| ParseError::DependencyCycle(from_filename.to_string(), cycle)));
+
+    for task in sorted_tasks.iter() {
+        try!(ff.add_task_obj(task)
+            .map_err(|e| ParseError::from_add_task_error(from_filename, e)));
+    }
+
     Ok(ff)
 }
@@ -62,8 +62,24 @@ pub fn validate_schema(json: &str, schema: &str) -> Result<(), String> {
     }
 }
 
-pub fn validate_against_factfile_schema(json: &str) -> Result<(), String> {
-    let factotum_schema_str: &'static str = include_str!("jsonschemas/factotum.json");
+pub const FACTFILE_SCHEMA_1_1_0: &'static str = "iglu:com.snowplowanalytics.factotum/factfile/\
+                                                  jsonschema/1-1-0";
+
+fn get_factfile_schema(json: &str) -> &'static str {
+    // published schemas never change, so newer fields are only allowed by newer versions
+    // (anything that isn't 1-1-0, including invalid json, is checked against 1-0-0 as before)
+    let schema_uri = Json::from_str(json)
+        .ok()
+        .and_then(|j| j.find("schema").and_then(|s| s.as_string()).map(|s| s.to_string()));
 
-    validate_schema(json, factotum_schema_str)
+    match schema_uri {
+        Some(ref uri) if uri == FACTFILE_SCHEMA_1_1_0 => {
+            include_str!("jsonschemas/factotum_1-1-0.json")
+        }
+        _ => include_str!("jsonschemas/factotum.json"),
+    }
+}
+
+pub fn validate_against_factfile_schema(json: &str) -> Result<(), String> {
+    validate_schema(json, get_factfile_schema(json))
 }
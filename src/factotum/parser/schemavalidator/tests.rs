@@ -12,5 +12,30 @@
 // governing permissions and limitations there under.
 //
 
+use super::*;
 
-// TODO write some tests for the schema validator
+const FACTFILE_SCHEMA_1_0_0: &'static str = "iglu:com.snowplowanalytics.factotum/factfile/\
+                                             jsonschema/1-0-0";
+
+fn make_factfile(schema: &str, extra_task_fields: &str) -> String {
+    format!("{{\"schema\":\"{}\",\"data\":{{\"name\":\"job\",\"tasks\":[{{\"name\":\"apple\",\
+             \"executor\":\"shell\",\"command\":\"echo\",\"arguments\":[],\"dependsOn\":[],\
+             \"onResult\":{{\"terminateJobWithSuccess\":[],\"continueJob\":[0]}}{}}}]}}}}",
+            schema,
+            extra_task_fields)
+}
+
+#[test]
+fn both_factfile_schema_versions_accepted() {
+    assert!(validate_against_factfile_schema(&make_factfile(FACTFILE_SCHEMA_1_0_0, "")).is_ok());
+    assert!(validate_against_factfile_schema(&make_factfile(FACTFILE_SCHEMA_1_1_0, "")).is_ok());
+}
+
+#[test]
+fn new_fields_need_schema_1_1_0() {
+    let timeout = ",\"timeoutSeconds\":30";
+    assert!(validate_against_factfile_schema(&make_factfile(FACTFILE_SCHEMA_1_0_0, timeout))
+        .is_err());
+    assert!(validate_against_factfile_schema(&make_factfile(FACTFILE_SCHEMA_1_1_0, timeout))
+        .is_ok());
+}
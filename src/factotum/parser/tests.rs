@@ -24,7 +24,7 @@ fn resource(name: &str) -> String {
 fn invalid_files_err() {
     let res = parse("asdhf;asdjhfasdf", None, OverrideResultMappings::None);
     if let Err(msg) = res {
-        assert_eq!(msg,
+        assert_eq!(msg.to_string(),
                    "Couldn't open 'asdhf;asdjhfasdf' for reading: No such file or directory (os \
                     error 2)"
                        .to_string())
@@ -39,7 +39,7 @@ fn invalid_json_err() {
                     None,
                     OverrideResultMappings::None);
     if let Err(msg) = res {
-        assert_eq!(msg,
+        assert_eq!(msg.to_string(),
                    format!("'{}' is not a valid factotum factfile: invalid JSON - invalid syntax \
                             at line 1, column 3",
                            resource("invalid_json.factfile"))
@@ -54,7 +54,7 @@ fn invalid_against_schema_err() {
     let invalid = resource("example_invalid_no_name.factfile");
     let res = parse(&invalid, None, OverrideResultMappings::None);
     if let Err(msg) = res {
-        assert_eq!(msg,
+        assert_eq!(msg.to_string(),
                    format!("'{}' is not a valid factotum factfile: '/data/name' - This property \
                             is required",
                            invalid)
@@ -69,7 +69,7 @@ fn invalid_against_schema_wrong_type() {
     let invalid = resource("example_wrong_type.factfile");
     let res = parse(&invalid, None, OverrideResultMappings::None);
     if let Err(msg) = res {
-        assert_eq!(msg,
+        assert_eq!(msg.to_string(),
                    format!("'{}' is not a valid factotum factfile: \
                             '/data/tasks/0/onResult/terminateJobWithSuccess/0' - Type of the \
                             value is wrong (The value must be integer)",
@@ -85,7 +85,7 @@ fn invalid_ambiguous_on_result() {
     let invalid = resource("example_invalid_terminate_continue_same.factfile");
     let res = parse(&invalid, None, OverrideResultMappings::None);
     if let Err(msg) = res {
-        assert_eq!(msg,
+        assert_eq!(msg.to_string(),
                    format!("'{}' is not a valid factotum factfile: the task 'ambi' has \
                             conflicting actions.",
                            invalid))
@@ -99,7 +99,7 @@ fn invalid_must_continue() {
     let invalid = resource("example_invalid_no_continue.factfile");
     let res = parse(&invalid, None, OverrideResultMappings::None);
     if let Err(msg) = res {
-        assert_eq!(msg,
+        assert_eq!(msg.to_string(),
                    format!("'{}' is not a valid factotum factfile: the task 'continue' has no \
                             way to continue successfully.",
                            invalid))
@@ -176,3 +176,276 @@ fn overrides_set_noop_values() {
     }
 
 }
+
+#[test]
+fn timeout_seconds_parsed() {
+    use std::time::Duration;
+    let valid = resource("example_timeout.factfile");
+
+    if let Ok(factfile) = parse(&valid, None, OverrideResultMappings::None) {
+        let tasks = factfile.get_tasks_in_order();
+
+        let task_one = tasks.get(0).unwrap().get(0).unwrap();
+        assert_eq!(task_one.name, "poll");
+        assert_eq!(task_one.timeout, Some(Duration::from_secs(600)));
+
+        let task_two = tasks.get(1).unwrap().get(0).unwrap();
+        assert_eq!(task_two.name, "load");
+        assert_eq!(task_two.timeout, None);
+
+        assert!(factfile.raw.contains("\"timeoutSeconds\":600"));
+        assert!(!factfile.raw.contains("null"));
+    } else {
+        panic!("valid factfile example_timeout.factfile should have parsed but didn't");
+    }
+}
+
+#[test]
+fn retry_policy_parsed() {
+    use std::time::Duration;
+    use factotum::factfile::{RetryPolicy, Backoff};
+    let valid = resource("example_retry.factfile");
+
+    if let Ok(factfile) = parse(&valid, None, OverrideResultMappings::None) {
+        let tasks = factfile.get_tasks_in_order();
+
+        let task_one = tasks.get(0).unwrap().get(0).unwrap();
+        assert_eq!(task_one.retry,
+                   Some(RetryPolicy {
+                       max_attempts: 3,
+                       delay: Duration::from_secs(5),
+                       backoff: Backoff::Exponential,
+                       retry_on: vec![1, 2],
+                   }));
+    } else {
+        panic!("valid factfile example_retry.factfile should have parsed but didn't");
+    }
+}
+
+#[test]
+fn invalid_retry_on_continue_code() {
+    let invalid = resource("example_invalid_retry_continue_same.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(msg) = res {
+        assert_eq!(msg.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the task 's3 copy' retries on \
+                            a return code that also continues or terminates the job.",
+                           invalid))
+    } else {
+        panic!("retrying on a continue code should fail");
+    }
+}
+
+#[test]
+fn on_signal_parsed() {
+    use libc;
+    let valid = resource("example_on_signal.factfile");
+
+    if let Ok(factfile) = parse(&valid, None, OverrideResultMappings::None) {
+        let tasks = factfile.get_tasks_in_order();
+
+        let task_one = tasks.get(0).unwrap().get(0).unwrap();
+        assert_eq!(task_one.on_result.continue_job_signals, vec![libc::SIGTERM]);
+        assert_eq!(task_one.on_result.terminate_job_signals, vec![libc::SIGUSR1]);
+
+        let task_two = tasks.get(1).unwrap().get(0).unwrap();
+        assert!(task_two.on_result.continue_job_signals.is_empty());
+        assert!(task_two.on_result.terminate_job_signals.is_empty());
+
+        assert!(factfile.raw.contains("\"onSignal\":{\"SIGTERM\":\"continue\""));
+        assert!(!factfile.raw.contains("null"));
+    } else {
+        panic!("valid factfile example_on_signal.factfile should have parsed but didn't");
+    }
+}
+
+#[test]
+fn invalid_unknown_signal() {
+    let invalid = resource("example_invalid_unknown_signal.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(msg) = res {
+        assert_eq!(msg.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the task 'sync' has an \
+                            unknown signal 'SIGPOTATO' in onSignal.",
+                           invalid))
+    } else {
+        panic!("an unknown signal in onSignal should fail");
+    }
+}
+
+#[test]
+fn env_and_working_directory_parsed() {
+    use rustc_serialize::json::Json;
+    let valid = resource("example_env.factfile");
+    let env = Json::from_str("{\"region\": \"eu-west-1\", \"app\": \"loader\"}").unwrap();
+
+    if let Ok(factfile) = parse(&valid, Some(env), OverrideResultMappings::None) {
+        let tasks = factfile.get_tasks_in_order();
+
+        let task_one = tasks.get(0).unwrap().get(0).unwrap();
+        assert_eq!(task_one.env.get("AWS_REGION"), Some(&"eu-west-1".to_string()));
+        assert_eq!(task_one.env.get("SOURCE"), Some(&"s3://bucket/in".to_string()));
+        assert_eq!(task_one.working_directory, Some("/opt/loader".to_string()));
+
+        let task_two = tasks.get(1).unwrap().get(0).unwrap();
+        assert!(task_two.env.is_empty());
+        assert_eq!(task_two.working_directory, None);
+
+        assert!(factfile.raw.contains("\"workingDirectory\":\"/opt/loader\""));
+        assert!(!factfile.raw.contains("null"));
+    } else {
+        panic!("valid factfile example_env.factfile should have parsed but didn't");
+    }
+}
+
+#[test]
+fn invalid_executor() {
+    let invalid = resource("example_invalid_executor.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(msg) = res {
+        assert_eq!(msg.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the task 'load one' uses the \
+                            executor 'docker', which isn't supported (the executor must be \
+                            'shell' or 'exec').",
+                           invalid))
+    } else {
+        panic!("using an unknown executor should fail");
+    }
+}
+
+#[test]
+fn pools_parsed() {
+    let valid = resource("example_pools.factfile");
+
+    if let Ok(factfile) = parse(&valid, None, OverrideResultMappings::None) {
+        assert_eq!(factfile.pools.get("redshift"), Some(&2));
+        assert!(factfile.raw.contains("\"pools\":{\"redshift\":2}"));
+
+        let tasks = factfile.get_tasks_in_order();
+        let pools = tasks[0].iter().map(|t| t.pool.clone()).collect::<Vec<Option<String>>>();
+        assert!(pools.contains(&Some("redshift".to_string())));
+        assert!(pools.contains(&None));
+    } else {
+        panic!("valid factfile example_pools.factfile should have parsed but didn't");
+    }
+}
+
+#[test]
+fn invalid_undefined_pool() {
+    let invalid = resource("example_invalid_undefined_pool.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(msg) = res {
+        assert_eq!(msg.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the task 'load one' uses the \
+                            pool 'redshift', which isn't defined in the factfile's pools.",
+                           invalid))
+    } else {
+        panic!("using an undefined pool should fail");
+    }
+}
+
+#[test]
+fn yaml_and_toml_factfiles_match_json() {
+    let json_ff = parse(&resource("example_ok.factfile"),
+                        None,
+                        OverrideResultMappings::None)
+        .unwrap();
+
+    for other in vec!["example_ok.yaml", "example_ok.toml"] {
+        match parse(&resource(other), None, OverrideResultMappings::None) {
+            Ok(ff) => {
+                assert_eq!(ff.name, json_ff.name);
+                assert_eq!(ff.raw, json_ff.raw);
+                assert_eq!(ff.get_tasks_in_order(), json_ff.get_tasks_in_order());
+            }
+            Err(msg) => panic!("valid factfile {} should have parsed but didn't: {}", other, msg),
+        }
+    }
+}
+
+#[test]
+fn format_overrides_extension() {
+    use factotum::parser::formats::Format;
+    let yaml = resource("example_ok.yaml");
+    assert!(parse_with_format(&yaml, Some(Format::Yaml), None, OverrideResultMappings::None)
+        .is_ok());
+    assert!(parse_with_format(&yaml, Some(Format::Json), None, OverrideResultMappings::None)
+        .is_err());
+}
+
+#[test]
+fn invalid_duplicate_task_name() {
+    let invalid = resource("example_invalid_duplicate_task.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(err) = res {
+        assert_eq!(err,
+                   ParseError::DuplicateTaskName(invalid.clone(), "apple".to_string()));
+        assert_eq!(err.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the task name 'apple' is \
+                            used more than once - task names must be unique.",
+                           invalid))
+    } else {
+        panic!("duplicate task names should fail");
+    }
+}
+
+#[test]
+fn invalid_self_dependency() {
+    let invalid = resource("example_invalid_self_dependency.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(err) = res {
+        assert_eq!(err, ParseError::SelfDependency(invalid.clone(), "apple".to_string()));
+        assert_eq!(err.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the task 'apple' lists \
+                            itself in dependsOn.",
+                           invalid))
+    } else {
+        panic!("a task depending on itself should fail");
+    }
+}
+
+#[test]
+fn invalid_undefined_dependency() {
+    let invalid = resource("example_invalid_undefined_dependency.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(err) = res {
+        assert_eq!(err,
+                   ParseError::UndefinedDependency(invalid.clone(),
+                                                   "apple".to_string(),
+                                                   "carrot".to_string()));
+        assert_eq!(err.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the task 'apple' has \
+                            'carrot' in dependsOn, but no task with that name is defined.",
+                           invalid))
+    } else {
+        panic!("depending on a task that isn't defined should fail");
+    }
+}
+
+#[test]
+fn tasks_declared_out_of_order() {
+    let in_order = parse(&resource("example_ok.factfile"),
+                         None,
+                         OverrideResultMappings::None)
+        .unwrap();
+    let out_of_order = parse(&resource("example_out_of_order.factfile"),
+                             None,
+                             OverrideResultMappings::None)
+        .unwrap();
+
+    assert_eq!(out_of_order.get_tasks_in_order(), in_order.get_tasks_in_order());
+}
+
+#[test]
+fn invalid_dependency_cycle() {
+    let invalid = resource("example_invalid_cycle.factfile");
+    let res = parse(&invalid, None, OverrideResultMappings::None);
+    if let Err(err) = res {
+        assert_eq!(err.to_string(),
+                   format!("'{}' is not a valid factotum factfile: the tasks' dependsOn form a \
+                            cycle - 'apple' -> 'carrot' -> 'banana' -> 'apple'.",
+                           invalid))
+    } else {
+        panic!("a dependency cycle should fail");
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+#[cfg(test)]
+mod tests;
+
+use factotum::executor::TaskSnapshot;
+use factotum::executor::task_list::State;
+use factotum::webhook::jobcontext::JobContext;
+use factotum::cancellation;
+use factotum::webhook::jobupdate::{TaskRunState, to_task_run_state, to_string_datetime,
+                                   tail_n_chars};
+use chrono::UTC;
+use chrono::duration::Duration as ChronoDuration;
+use rustc_serialize::json;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Result<ReportFormat, String> {
+        match name.to_lowercase().as_ref() {
+            "json" => Ok(ReportFormat::Json),
+            "junit" => Ok(ReportFormat::Junit),
+            _ => Err(format!("unknown report format '{}' (expected json or junit)", name)),
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub enum JobOutcome {
+    SUCCEEDED,
+    FINISHED_EARLY,
+    FAILED,
+    CANCELLED,
+}
+
+#[derive(RustcEncodable, Debug)]
+#[allow(non_snake_case)]
+pub struct TaskReport {
+    pub taskName: String,
+    pub state: TaskRunState,
+    pub reason: Option<String>,
+    pub started: Option<String>,
+    pub duration: Option<String>,
+    pub durationSeconds: Option<f64>,
+    pub returnCode: Option<i32>,
+    pub terminatingSignal: Option<String>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+}
+
+#[derive(RustcEncodable, Debug)]
+#[allow(non_snake_case)]
+pub struct RunReport {
+    pub jobName: String,
+    pub jobReference: String,
+    pub runReference: String,
+    pub factotumVersion: String,
+    pub startTime: String,
+    pub runDuration: String,
+    pub outcome: JobOutcome,
+    pub exitCode: i32,
+    pub tags: HashMap<String, String>,
+    pub tasks: Vec<TaskReport>,
+}
+
+impl RunReport {
+    pub fn new(context: &JobContext,
+               tasks: &TaskSnapshot,
+               outcome: JobOutcome,
+               exit_code: i32,
+               max_stdouterr_size: usize)
+               -> Self {
+        RunReport {
+            jobName: context.job_name.clone(),
+            jobReference: context.job_reference.clone(),
+            runReference: context.run_reference.clone(),
+            factotumVersion: context.factotum_version.clone(),
+            startTime: to_string_datetime(&context.start_time),
+            runDuration: (UTC::now() - context.start_time).to_string(),
+            outcome: outcome,
+            exitCode: exit_code,
+            tags: context.tags.clone(),
+            tasks: tasks.iter()
+                .map(|task| {
+                    let result = task.run_result.as_ref();
+                    TaskReport {
+                        taskName: task.name.clone(),
+                        state: to_task_run_state(&task.state),
+                        reason: match task.state {
+                            State::Failed(ref reason) |
+                            State::Skipped(ref reason) |
+                            State::Cancelled(ref reason) => Some(reason.clone()),
+                            _ => None,
+                        },
+                        started: task.run_started.as_ref().map(to_string_datetime),
+                        duration: result.map(|r| {
+                            ChronoDuration::from_std(r.duration).unwrap().to_string()
+                        }),
+                        durationSeconds: result.map(|r| as_seconds(&r.duration)),
+                        returnCode: result.map(|r| r.return_code),
+                        terminatingSignal: result.and_then(|r| r.terminating_signal)
+                            .map(cancellation::signal_name),
+                        stdout: result.and_then(|r| r.stdout.as_ref())
+                            .map(|o| tail_n_chars(o, max_stdouterr_size).to_string()),
+                        stderr: result.and_then(|r| r.stderr.as_ref())
+                            .map(|e| tail_n_chars(e, max_stdouterr_size).to_string()),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => format!("{}\n", json::as_pretty_json(self)),
+            ReportFormat::Junit => self.render_junit(),
+        }
+    }
+
+    fn render_junit(&self) -> String {
+        // one testsuite for the job, with a testcase for each task
+        let count_state = |state: TaskRunState| {
+            self.tasks.iter().filter(|t| t.state == state).count()
+        };
+        let total_time = self.tasks
+            .iter()
+            .filter_map(|t| t.durationSeconds)
+            .fold(0.0, |a, b| a + b);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!("<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" \
+                               skipped=\"{}\" time=\"{:.3}\" timestamp=\"{}\">\n",
+                              xml_escape(&self.jobName),
+                              self.tasks.len(),
+                              count_state(TaskRunState::FAILED),
+                              count_state(TaskRunState::SKIPPED),
+                              total_time,
+                              xml_escape(&self.startTime)));
+
+        for task in self.tasks.iter() {
+            let time = match task.durationSeconds {
+                Some(secs) => format!(" time=\"{:.3}\"", secs),
+                None => String::new(),
+            };
+            xml.push_str(&format!("  <testcase name=\"{}\" classname=\"{}\"{}>\n",
+                                  xml_escape(&task.taskName),
+                                  xml_escape(&self.jobName),
+                                  time));
+
+            let reason = task.reason.as_ref().map(|r| xml_escape(r)).unwrap_or_default();
+            match task.state {
+                TaskRunState::FAILED => {
+                    xml.push_str(&format!("    <failure message=\"{}\">{}</failure>\n",
+                                          reason,
+                                          reason))
+                }
+                TaskRunState::SKIPPED => {
+                    xml.push_str(&format!("    <skipped message=\"{}\"/>\n", reason))
+                }
+                _ => (),
+            }
+
+            if let Some(ref stdout) = task.stdout {
+                xml.push_str(&format!("    <system-out>{}</system-out>\n", xml_escape(stdout)));
+            }
+            if let Some(ref stderr) = task.stderr {
+                xml.push_str(&format!("    <system-err>{}</system-err>\n", xml_escape(stderr)));
+            }
+
+            xml.push_str("  </testcase>\n");
+        }
+
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+}
+
+fn as_seconds(duration: &Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // control characters (other than whitespace) aren't allowed in XML 1.0 at all
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
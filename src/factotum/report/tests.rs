@@ -0,0 +1,144 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use super::*;
+use factotum::executor::execution_strategy::RunResult;
+use factotum::executor::task_list::Task;
+use factotum::tests::make_task;
+use rustc_serialize::json::Json;
+use chrono::UTC;
+use std::time::Duration;
+
+fn make_snapshot() -> TaskSnapshot {
+    let mut apple = Task::new("apple", make_task("apple", &vec![]));
+    apple.state = State::Success;
+    apple.run_started = Some(UTC::now());
+    apple.run_result = Some(RunResult {
+        duration: Duration::from_secs(2),
+        task_execution_error: None,
+        stdout: Some("hello world".to_string()),
+        stderr: None,
+        return_code: 0,
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
+    });
+
+    let mut banana = Task::new("banana", make_task("banana", &vec!["apple"]));
+    banana.state = State::Failed("the task exited with 1".to_string());
+    banana.run_started = Some(UTC::now());
+    banana.run_result = Some(RunResult {
+        duration: Duration::from_secs(1),
+        task_execution_error: None,
+        stdout: None,
+        stderr: Some("it went wrong".to_string()),
+        return_code: 1,
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
+    });
+
+    let mut carrot = Task::new("carrot", make_task("carrot", &vec!["banana"]));
+    carrot.state = State::Skipped("the task 'banana' failed".to_string());
+
+    vec![apple, banana, carrot]
+}
+
+#[test]
+fn report_format_from_name() {
+    assert_eq!(ReportFormat::from_name("json"), Ok(ReportFormat::Json));
+    assert_eq!(ReportFormat::from_name("JSON"), Ok(ReportFormat::Json));
+    assert_eq!(ReportFormat::from_name("junit"), Ok(ReportFormat::Junit));
+    assert!(ReportFormat::from_name("xml").is_err());
+}
+
+#[test]
+fn report_has_job_details() {
+    let context = JobContext::new("job", "{}", None);
+    let report = RunReport::new(&context, &make_snapshot(), JobOutcome::FAILED, 2, 10_000);
+
+    assert_eq!(report.jobName, "job");
+    assert_eq!(report.jobReference, context.job_reference);
+    assert_eq!(report.runReference, context.run_reference);
+    assert_eq!(report.outcome, JobOutcome::FAILED);
+    assert_eq!(report.exitCode, 2);
+    assert_eq!(report.tasks.len(), 3);
+}
+
+#[test]
+fn report_has_task_details() {
+    let context = JobContext::new("job", "{}", None);
+    let report = RunReport::new(&context, &make_snapshot(), JobOutcome::FAILED, 2, 5);
+
+    assert_eq!(report.tasks[0].state, TaskRunState::SUCCEEDED);
+    assert_eq!(report.tasks[0].returnCode, Some(0));
+    assert_eq!(report.tasks[0].stdout, Some("world".to_string()));
+    assert_eq!(report.tasks[0].reason, None);
+
+    assert_eq!(report.tasks[1].state, TaskRunState::FAILED);
+    assert_eq!(report.tasks[1].returnCode, Some(1));
+    assert_eq!(report.tasks[1].stderr, Some("wrong".to_string()));
+    assert_eq!(report.tasks[1].reason,
+               Some("the task exited with 1".to_string()));
+
+    assert_eq!(report.tasks[2].state, TaskRunState::SKIPPED);
+    assert_eq!(report.tasks[2].started, None);
+    assert_eq!(report.tasks[2].returnCode, None);
+    assert_eq!(report.tasks[2].reason,
+               Some("the task 'banana' failed".to_string()));
+}
+
+#[test]
+fn report_renders_json() {
+    let context = JobContext::new("job", "{}", None);
+    let report = RunReport::new(&context, &make_snapshot(), JobOutcome::FAILED, 2, 10_000);
+
+    let rendered = Json::from_str(&report.render(ReportFormat::Json)).unwrap();
+
+    assert_eq!(rendered.find("outcome").unwrap().as_string(), Some("FAILED"));
+    assert_eq!(rendered.find("exitCode").unwrap().as_i64(), Some(2));
+    let tasks = rendered.find("tasks").unwrap().as_array().unwrap();
+    assert_eq!(tasks[1].find("taskName").unwrap().as_string(), Some("banana"));
+    assert_eq!(tasks[1].find("state").unwrap().as_string(), Some("FAILED"));
+}
+
+#[test]
+fn report_renders_junit() {
+    let context = JobContext::new("job <1>", "{}", None);
+    let report = RunReport::new(&context, &make_snapshot(), JobOutcome::FAILED, 2, 10_000);
+
+    let rendered = report.render(ReportFormat::Junit);
+
+    assert!(rendered.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuite \
+                                  name=\"job &lt;1&gt;\" tests=\"3\" failures=\"1\" \
+                                  errors=\"0\" skipped=\"1\" time=\"3.000\""));
+    assert!(rendered.contains("  <testcase name=\"apple\" classname=\"job &lt;1&gt;\" \
+                               time=\"2.000\">\n    <system-out>hello world</system-out>\n  \
+                               </testcase>\n"));
+    assert!(rendered.contains("    <failure message=\"the task exited with 1\">the task exited \
+                               with 1</failure>\n    <system-err>it went wrong</system-err>\n"));
+    assert!(rendered.contains("  <testcase name=\"carrot\" classname=\"job &lt;1&gt;\">\n    \
+                               <skipped message=\"the task &apos;banana&apos; failed\"/>\n"));
+    assert!(rendered.ends_with("</testsuite>\n"));
+}
+
+#[test]
+fn xml_is_escaped() {
+    assert_eq!(super::xml_escape("a & b < c > \"d\" 'e'\u{1b}[0m\n"),
+               "a &amp; b &lt; c &gt; &quot;d&quot; &apos;e&apos;[0m\n");
+}
@@ -0,0 +1,105 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+#[cfg(test)]
+mod tests;
+
+use factotum::executor::TaskSnapshot;
+use factotum::executor::task_list::State;
+use factotum::webhook::jobcontext::JobContext;
+use rustc_serialize::json;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+
+pub const RUN_STATE_DIR: &'static str = ".factotum/runs";
+
+#[derive(RustcEncodable, RustcDecodable, Clone, Debug, PartialEq)]
+pub struct TaskRunState {
+    pub name: String,
+    pub state: State,
+}
+
+#[derive(RustcEncodable, RustcDecodable, Clone, Debug, PartialEq)]
+pub struct RunState {
+    pub job_name: String,
+    pub job_reference: String,
+    pub run_reference: String,
+    pub factfile: String,
+    pub tags: HashMap<String, String>,
+    pub start_from: Option<String>,
+    pub tasks: Vec<TaskRunState>,
+}
+
+impl RunState {
+    pub fn new(job_context: &JobContext,
+               factfile: &str,
+               start_from: Option<String>,
+               tasks: &TaskSnapshot)
+               -> Self {
+        RunState {
+            job_name: job_context.job_name.clone(),
+            job_reference: job_context.job_reference.clone(),
+            run_reference: job_context.run_reference.clone(),
+            factfile: factfile.to_string(),
+            tags: job_context.tags.clone(),
+            start_from: start_from,
+            tasks: tasks.iter()
+                .map(|t| {
+                    TaskRunState {
+                        name: t.name.clone(),
+                        state: t.state.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn load(filename: &str) -> Result<RunState, String> {
+        let mut fh = try!(File::open(filename)
+            .map_err(|e| format!("Couldn't open '{}' for reading: {}", filename, e)));
+        let mut contents = String::new();
+        try!(fh.read_to_string(&mut contents)
+            .map_err(|e| format!("Couldn't read '{}': {}", filename, e)));
+        json::decode(&contents)
+            .map_err(|e| format!("'{}' is not a valid factotum run state file: {}", filename, e))
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        if let Some(dir) = Path::new(filename).parent() {
+            try!(fs::create_dir_all(dir)
+                .map_err(|e| format!("couldn't create directory '{}' ({})", dir.display(), e)));
+        }
+        let contents = try!(json::encode(self).map_err(|e| e.to_string()));
+        let mut fh = try!(File::create(filename)
+            .map_err(|e| format!("couldn't create file '{}' ({})", filename, e)));
+        fh.write_all(contents.as_bytes())
+            .map_err(|e| format!("couldn't write to file '{}' ({})", filename, e))
+    }
+
+    pub fn get_default_filename(&self) -> String {
+        format!("{}/{}.json", RUN_STATE_DIR, self.run_reference)
+    }
+
+    // the tasks that don't need to run again - anything that failed, or was skipped because of
+    // a failure, is left out so it gets retried
+    pub fn get_completed_tasks(&self) -> HashMap<String, State> {
+        self.tasks
+            .iter()
+            .filter(|t| t.state == State::Success || t.state == State::SuccessNoop)
+            .map(|t| (t.name.clone(), t.state.clone()))
+            .collect()
+    }
+}
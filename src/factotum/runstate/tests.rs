@@ -0,0 +1,100 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use super::*;
+use factotum::executor::task_list::{State, Task};
+use factotum::tests::make_task;
+use std::env;
+
+fn make_snapshot() -> TaskSnapshot {
+    let states = vec![("apple", State::Success),
+                      ("banana", State::SuccessNoop),
+                      ("carrot", State::Failed("the task 'carrot' failed".to_string())),
+                      ("damson", State::Skipped("the task 'carrot' failed".to_string()))];
+    states.into_iter()
+        .map(|(name, state)| {
+            let mut task = Task::new(name, make_task(name, &vec![]));
+            task.state = state;
+            task
+        })
+        .collect()
+}
+
+#[test]
+fn new_copies_job_context() {
+    let mut tags = HashMap::new();
+    tags.insert("hello".to_string(), "world".to_string());
+    let context = JobContext::new("job", "{}", Some(tags.clone()));
+
+    let run_state = RunState::new(&context, "{}", None, &make_snapshot());
+
+    assert_eq!(run_state.job_name, "job");
+    assert_eq!(run_state.job_reference, context.job_reference);
+    assert_eq!(run_state.run_reference, context.run_reference);
+    assert_eq!(run_state.factfile, "{}");
+    assert_eq!(run_state.tags, tags);
+    assert_eq!(run_state.start_from, None);
+    assert_eq!(run_state.tasks.len(), 4);
+}
+
+#[test]
+fn completed_tasks_exclude_failed_and_skipped() {
+    let context = JobContext::new("job", "{}", None);
+    let run_state = RunState::new(&context, "{}", None, &make_snapshot());
+
+    let completed = run_state.get_completed_tasks();
+
+    assert_eq!(completed.len(), 2);
+    assert_eq!(completed.get("apple"), Some(&State::Success));
+    assert_eq!(completed.get("banana"), Some(&State::SuccessNoop));
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let context = JobContext::new("job", "{}", None);
+    let run_state = RunState::new(&context,
+                                  "{}",
+                                  Some("banana".to_string()),
+                                  &make_snapshot());
+
+    let dir = env::temp_dir().join(format!("factotum-run-state-{}", run_state.run_reference));
+    let filename = dir.join("state.json").to_string_lossy().into_owned();
+
+    run_state.save(&filename).unwrap();
+    let loaded = RunState::load(&filename).unwrap();
+    fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(loaded, run_state);
+}
+
+#[test]
+fn load_without_start_from_ok() {
+    let dir = env::temp_dir().join("factotum-run-state-no-start-from");
+    let filename = dir.join("state.json").to_string_lossy().into_owned();
+    fs::create_dir_all(&dir).unwrap();
+    File::create(&filename)
+        .unwrap()
+        .write_all(br#"{"job_name":"job","job_reference":"abc","run_reference":"def","factfile":"{}","tags":{},"tasks":[]}"#)
+        .unwrap();
+
+    let loaded = RunState::load(&filename);
+    fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(loaded.unwrap().start_from, None);
+}
+
+#[test]
+fn load_missing_file_err() {
+    assert!(RunState::load("/does/not/exist.json").is_err());
+}
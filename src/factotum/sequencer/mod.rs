@@ -101,3 +101,55 @@ pub fn is_proper_sub_tree(dag: &Dag<Task, ()>, start: NodeIndex) -> bool {
 
     true
 }
+
+pub fn sort_by_dependencies(tasks: Vec<Task>) -> Result<Vec<Task>, Vec<String>> {
+    // orders the tasks so each comes after everything it depends on, keeping the declared order
+    // where possible - a cycle is returned as the names of the tasks around it
+    let mut visited = vec![false; tasks.len()];
+    let mut order = vec![];
+
+    for idx in 0..tasks.len() {
+        try!(visit_dependencies_first(&tasks, idx, &mut visited, &mut vec![], &mut order));
+    }
+
+    let mut unsorted: Vec<Option<Task>> = tasks.into_iter().map(Some).collect();
+    Ok(order.into_iter().map(|idx| unsorted[idx].take().unwrap()).collect())
+}
+
+fn visit_dependencies_first(tasks: &Vec<Task>,
+                            idx: usize,
+                            visited: &mut Vec<bool>,
+                            path: &mut Vec<usize>,
+                            order: &mut Vec<usize>)
+                            -> Result<(), Vec<String>> {
+    if visited[idx] {
+        return Ok(());
+    }
+
+    if let Some(cycle_start) = path.iter().position(|p| *p == idx) {
+        let mut cycle = path[cycle_start..]
+            .iter()
+            .map(|p| tasks[*p].name.clone())
+            .collect::<Vec<String>>();
+        cycle.push(tasks[idx].name.clone());
+        return Err(cycle);
+    }
+
+    path.push(idx);
+    for dependency in tasks[idx].depends_on.iter() {
+        // self references and missing tasks are left for Factfile::add_task_obj to report
+        if *dependency == tasks[idx].name {
+            continue;
+        }
+        for dep_idx in 0..tasks.len() {
+            if tasks[dep_idx].name == *dependency {
+                try!(visit_dependencies_first(tasks, dep_idx, visited, path, order));
+            }
+        }
+    }
+    path.pop();
+
+    visited[idx] = true;
+    order.push(idx);
+    Ok(())
+}
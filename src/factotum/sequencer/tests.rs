@@ -123,3 +123,52 @@ fn check_invalid_subtree() {
 
     assert_eq!(true, super::is_proper_sub_tree(&dag, root_idx));
 }
+
+#[test]
+fn sort_by_dependencies_keeps_declared_order() {
+    let tasks = vec![make_task("apple", &vec![]),
+                     make_task("turnip", &vec![]),
+                     make_task("orange", &vec!["apple"]),
+                     make_task("potato", &vec!["apple", "turnip"])];
+
+    let sorted = super::sort_by_dependencies(tasks.clone()).unwrap();
+
+    assert_eq!(sorted, tasks);
+}
+
+#[test]
+fn sort_by_dependencies_moves_parents_first() {
+    let tasks = vec![make_task("potato", &vec!["orange", "turnip"]),
+                     make_task("orange", &vec!["apple"]),
+                     make_task("apple", &vec![]),
+                     make_task("turnip", &vec![])];
+
+    let sorted = super::sort_by_dependencies(tasks)
+        .unwrap()
+        .iter()
+        .map(|t| t.name.clone())
+        .collect::<Vec<String>>();
+
+    assert_eq!(sorted, vec!["apple", "orange", "turnip", "potato"]);
+}
+
+#[test]
+fn sort_by_dependencies_reports_cycle() {
+    let tasks = vec![make_task("apple", &vec!["carrot"]),
+                     make_task("banana", &vec!["apple"]),
+                     make_task("carrot", &vec!["banana"]),
+                     make_task("damson", &vec![])];
+
+    assert_eq!(super::sort_by_dependencies(tasks),
+               Err(vec!["apple".to_string(),
+                        "carrot".to_string(),
+                        "banana".to_string(),
+                        "apple".to_string()]));
+}
+
+#[test]
+fn sort_by_dependencies_ignores_self_and_missing() {
+    let tasks = vec![make_task("apple", &vec!["apple", "missing"])];
+
+    assert_eq!(super::sort_by_dependencies(tasks.clone()), Ok(tasks));
+}
@@ -0,0 +1,69 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+#[cfg(test)]
+mod tests;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// where one attempt at running a task writes its stdout and stderr
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttemptLogs {
+    pub stdout: PathBuf,
+    pub stderr: PathBuf,
+}
+
+pub fn get_run_log_dir(log_dir: &str, run_reference: &str) -> PathBuf {
+    Path::new(log_dir).join(run_reference)
+}
+
+pub fn create_run_log_dir(log_dir: &str, run_reference: &str) -> Result<PathBuf, String> {
+    let run_dir = get_run_log_dir(log_dir, run_reference);
+    try!(fs::create_dir_all(&run_dir)
+        .map_err(|e| format!("couldn't create directory '{}' ({})", run_dir.display(), e)));
+    Ok(run_dir)
+}
+
+// task names are free text, so anything that would change the directory is percent-encoded
+// (along with '%' itself, so that no two task names share a file)
+pub fn get_log_filename(task_name: &str, attempt: u32, extension: &str) -> String {
+    let mut safe_name = String::new();
+    for c in task_name.chars() {
+        match c {
+            '%' => safe_name.push_str("%25"),
+            '/' => safe_name.push_str("%2F"),
+            '\\' => safe_name.push_str("%5C"),
+            _ => safe_name.push(c),
+        }
+    }
+    format!("{}.{}.{}", safe_name, attempt, extension)
+}
+
+// the logs for the task's next attempt in this run - numbering carries on from any attempts
+// that already have logs (including those from before the run was resumed) so nothing is
+// overwritten
+pub fn next_attempt_logs(run_dir: &Path, task_name: &str) -> AttemptLogs {
+    let mut attempt = 1;
+    loop {
+        let logs = AttemptLogs {
+            stdout: run_dir.join(get_log_filename(task_name, attempt, "out")),
+            stderr: run_dir.join(get_log_filename(task_name, attempt, "err")),
+        };
+        if !logs.stdout.exists() && !logs.stderr.exists() {
+            return logs;
+        }
+        attempt += 1;
+    }
+}
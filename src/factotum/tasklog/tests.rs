@@ -0,0 +1,65 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use super::*;
+use std::env;
+use std::fs::File;
+
+#[test]
+fn log_filenames_stay_in_the_run_dir() {
+    assert_eq!(get_log_filename("apple", 1, "out"), "apple.1.out");
+    assert_eq!(get_log_filename("../apple/pie", 2, "err"), "..%2Fapple%2Fpie.2.err");
+    assert_eq!(get_log_filename("apple\\pie", 1, "out"), "apple%5Cpie.1.out");
+}
+
+#[test]
+fn log_filenames_dont_collide() {
+    let names = vec!["a/b", "a_b", "a%2Fb", "a\\b", "a%5Cb"];
+    let mut filenames = names.iter().map(|n| get_log_filename(n, 1, "out")).collect::<Vec<_>>();
+    filenames.sort();
+    filenames.dedup();
+    assert_eq!(filenames.len(), names.len());
+}
+
+#[test]
+fn attempt_logs_numbered_from_one() {
+    let mut run_dir = env::temp_dir();
+    run_dir.push("factotum-tasklog-test-numbering");
+    fs::remove_dir_all(&run_dir).ok();
+    fs::create_dir_all(&run_dir).unwrap();
+
+    let first = next_attempt_logs(&run_dir, "apple");
+    assert_eq!(first.stdout, run_dir.join("apple.1.out"));
+    assert_eq!(first.stderr, run_dir.join("apple.1.err"));
+
+    File::create(&first.stdout).unwrap();
+    let second = next_attempt_logs(&run_dir, "apple");
+    assert_eq!(second.stdout, run_dir.join("apple.2.out"));
+    assert_eq!(second.stderr, run_dir.join("apple.2.err"));
+
+    fs::remove_dir_all(&run_dir).ok();
+}
+
+#[test]
+fn run_log_dir_created() {
+    let mut log_dir = env::temp_dir();
+    log_dir.push("factotum-tasklog-test-run-dir");
+    fs::remove_dir_all(&log_dir).ok();
+
+    let run_dir = create_run_log_dir(&log_dir.display().to_string(), "run-ref").unwrap();
+
+    assert_eq!(run_dir, log_dir.join("run-ref"));
+    assert!(run_dir.is_dir());
+    fs::remove_dir_all(&log_dir).ok();
+}
@@ -14,6 +14,7 @@
 
 use factotum::factfile::Task;
 use factotum::factfile::OnResult;
+use std::collections::HashMap;
 
 pub fn compare_tasks(expected: Vec<Vec<&str>>, actual: Vec<Vec<&Task>>) {
     for i in 0..expected.len() {
@@ -37,6 +38,13 @@ pub fn make_task(name: &str, depends_on: &Vec<&str>) -> Task {
         on_result: OnResult {
             terminate_job: vec![],
             continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
         },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
     }
 }
@@ -29,7 +29,9 @@ use rustc_serialize::Encodable;
 use rustc_serialize;
 use rustc_serialize::json::{self, ToJson, Json};
 use factotum::executor::task_list::State;
+use factotum::cancellation;
 use std::collections::HashMap;
+use std::path::Path;
 
 #[derive(RustcDecodable, RustcEncodable, Debug, PartialEq)]
 pub enum JobRunState {
@@ -175,10 +177,10 @@ fn to_job_run_state(state: &ExecutionState, tasks: &TaskSnapshot) -> JobRunState
     match *state {
         ExecutionState::Started => JobRunState::WAITING,
         ExecutionState::Finished => {
-            // if any tasks failed, set to failed
+            // if any tasks failed (or were cancelled), set to failed
             let failed_tasks = tasks.iter()
                 .any(|t| match t.state {
-                    State::Failed(_) => true,
+                    State::Failed(_) | State::Cancelled(_) => true,
                     _ => false,
                 });
             if failed_tasks {
@@ -245,22 +247,8 @@ impl JobUpdate {
                             .map(|t| {
                                 TaskTransition {
                                     taskName: t.task_name.clone(),
-                                    previousState: match t.from_state {
-                                        State::Waiting => TaskRunState::WAITING,
-                                        State::Running => TaskRunState::RUNNING,
-                                        State::Skipped(_) => TaskRunState::SKIPPED,
-                                        State::Success => TaskRunState::SUCCEEDED,
-                                        State::SuccessNoop => TaskRunState::SUCCEEDED_NO_OP,
-                                        State::Failed(_) => TaskRunState::FAILED,
-                                    },
-                                    currentState: match t.to_state {
-                                        State::Waiting => TaskRunState::WAITING,
-                                        State::Running => TaskRunState::RUNNING,
-                                        State::Skipped(_) => TaskRunState::SKIPPED,
-                                        State::Success => TaskRunState::SUCCEEDED,
-                                        State::SuccessNoop => TaskRunState::SUCCEEDED_NO_OP,
-                                        State::Failed(_) => TaskRunState::FAILED,
-                                    },
+                                    previousState: to_task_run_state(&t.from_state),
+                                    currentState: to_task_run_state(&t.to_state),
                                 }
                             })
                             .collect();
@@ -290,14 +278,7 @@ impl JobUpdate {
             .map(|task| {
                 TaskUpdate {
                     taskName: task.name.clone(),
-                    state: match task.state {
-                        State::Waiting => TaskRunState::WAITING,
-                        State::Running => TaskRunState::RUNNING,
-                        State::Skipped(_) => TaskRunState::SKIPPED,
-                        State::Success => TaskRunState::SUCCEEDED,
-                        State::SuccessNoop => TaskRunState::SUCCEEDED_NO_OP,
-                        State::Failed(_) => TaskRunState::FAILED,
-                    },
+                    state: to_task_run_state(&task.state),
                     started: if let Some(ref r) = task.run_started {
                         Some(to_string_datetime(r))
                     } else {
@@ -310,7 +291,9 @@ impl JobUpdate {
                     },
                     stdout: if let Some(ref r) = task.run_result {
                         if let Some(ref stdout) = r.stdout {
-                            Some(tail_n_chars(stdout, *max_stdouterr_size).into())
+                            Some(tail_output(stdout,
+                                             *max_stdouterr_size,
+                                             r.logs.as_ref().map(|l| l.stdout.as_path())))
                         } else {
                             None
                         }
@@ -319,7 +302,9 @@ impl JobUpdate {
                     },
                     stderr: if let Some(ref r) = task.run_result {
                         if let Some(ref stderr) = r.stderr {
-                            Some(tail_n_chars(stderr, *max_stdouterr_size).into())
+                            Some(tail_output(stderr,
+                                             *max_stdouterr_size,
+                                             r.logs.as_ref().map(|l| l.stderr.as_path())))
                         } else {
                             None
                         }
@@ -332,7 +317,8 @@ impl JobUpdate {
                         None
                     },
                     errorMessage: match (&task.state, &task.run_result) {
-                        (&State::Skipped(ref reason), _) => Some(reason.clone()),
+                        (&State::Skipped(ref reason), _) |
+                        (&State::Cancelled(ref reason), _) => Some(reason.clone()),
                         (&State::Failed(ref reason), &Some(ref result)) => {
                             if let Some(ref execution_error) = result.task_execution_error {
                                 Some(execution_error.clone())
@@ -340,6 +326,12 @@ impl JobUpdate {
                                 Some(reason.clone())
                             }                            
                         },
+                        // the schema has nowhere else to say a task was killed by a signal
+                        (_, &Some(ref result)) if result.terminating_signal.is_some() => {
+                            Some(format!("the task was killed by {}",
+                                         cancellation::signal_name(result.terminating_signal
+                                             .unwrap())))
+                        }
                         _ => None   
                     },
                 }
@@ -398,6 +390,18 @@ impl ToJson for JobUpdate {
     }
 }
 
+pub fn to_task_run_state(state: &State) -> TaskRunState {
+    match *state {
+        State::Waiting => TaskRunState::WAITING,
+        State::Running => TaskRunState::RUNNING,
+        State::Skipped(_) => TaskRunState::SKIPPED,
+        State::Success => TaskRunState::SUCCEEDED,
+        State::SuccessNoop => TaskRunState::SUCCEEDED_NO_OP,
+        State::Failed(_) => TaskRunState::FAILED,
+        State::Cancelled(_) => TaskRunState::FAILED,
+    }
+}
+
 pub fn to_string_datetime(datetime: &chrono::DateTime<UTC>) -> String {
     datetime.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
 }
@@ -412,4 +416,18 @@ pub fn tail_n_chars(s: &str, n: usize) -> &str {
             s
         }
     }
-}
\ No newline at end of file
+}
+
+// only the end of the output fits in an update, so when some has been cut off the update says
+// where the rest can be found (if it was saved)
+pub fn tail_output(output: &str, n: usize, log: Option<&Path>) -> String {
+    let tail = tail_n_chars(output, n);
+    match log {
+        Some(path) if tail.len() < output.len() => {
+            format!("[truncated, the full output is in '{}']\n{}",
+                    path.display(),
+                    tail)
+        }
+        _ => tail.to_string(),
+    }
+}
@@ -56,12 +56,12 @@ fn to_json_valid_against_schema_task_transition_running_to_failed() {
                                json");
 
     let mut ff = Factfile::new("N/A", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     let mut tasks = get_task_snapshot(&get_task_execution_list(&ff, None));
 
@@ -102,12 +102,12 @@ fn to_json_valid_against_schema_task_transition_waiting_to_running() {
                                json");
 
     let mut ff = Factfile::new("N/A", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     let mut tasks = get_task_snapshot(&get_task_execution_list(&ff, None));
 
@@ -173,12 +173,12 @@ fn headers_correct() {
 #[test]
 fn failed_headers_correct() {
     let mut ff = Factfile::new("N/A", "test");
-    ff.add_task_obj(&make_task("apple", &vec![]));
-    ff.add_task_obj(&make_task("turnip", &vec![]));
-    ff.add_task_obj(&make_task("orange", &vec!["apple"]));
-    ff.add_task_obj(&make_task("egg", &vec!["apple"]));
-    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"]));
-    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"]));
+    ff.add_task_obj(&make_task("apple", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("turnip", &vec![])).unwrap();
+    ff.add_task_obj(&make_task("orange", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("egg", &vec!["apple"])).unwrap();
+    ff.add_task_obj(&make_task("potato", &vec!["apple", "egg"])).unwrap();
+    ff.add_task_obj(&make_task("chicken", &vec!["potato", "orange"])).unwrap();
 
     let mut tasks = get_task_snapshot(&get_task_execution_list(&ff, None));
 
@@ -244,6 +244,11 @@ fn task_states_converted_with_run_data() {
         stderr: Some("banana".to_string()),
         stdout: Some("get".to_string()),
         duration: Duration::seconds(0).to_std().unwrap(),
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     });
 
     example_tasks[1].state = State::Success;
@@ -254,6 +259,11 @@ fn task_states_converted_with_run_data() {
         stderr: None,
         stdout: None,
         duration: Duration::seconds(1).to_std().unwrap(),
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     });
 
     let start_sample =
@@ -333,6 +343,11 @@ fn big_task_stdout_trimmed() {
         stderr: None,
         stdout: Some(format!("{}tail", make_n_char_string(20000))), // too long
         duration: Duration::seconds(0).to_std().unwrap(),
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     });
 
     example_tasks[1].state = State::Success;
@@ -343,6 +358,11 @@ fn big_task_stdout_trimmed() {
         stderr: None,
         stdout: Some(format!("{}tail", make_n_char_string(max_len-"tail".len()))), // just fits
         duration: Duration::seconds(1).to_std().unwrap(),
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     });
 
 
@@ -392,6 +412,11 @@ fn big_task_stderr_trimmed() {
         stderr: Some(format!("{}tail", make_n_char_string(20000))), // too long,
         stdout: None,
         duration: Duration::seconds(0).to_std().unwrap(),
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     });
 
     example_tasks[1].state = State::Success;
@@ -402,6 +427,11 @@ fn big_task_stderr_trimmed() {
         stderr: Some(format!("{}tail", make_n_char_string(max_len-"tail".len()))),
         stdout: None, // just fits
         duration: Duration::seconds(1).to_std().unwrap(),
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
     });
 
 
@@ -449,3 +479,26 @@ fn tail_n_chars_zero() {
     let twenty_character_str = make_n_char_string(20);
     assert_eq!(tail_n_chars(&twenty_character_str, 0), "");
 }
+
+#[test]
+fn tail_output_references_the_log_when_truncated() {
+    use std::path::Path;
+
+    let log = Path::new("/logs/run/apple.1.out");
+    assert_eq!(tail_output("lorem ipsum egg", 3, Some(log)),
+               "[truncated, the full output is in '/logs/run/apple.1.out']\negg");
+    assert_eq!(tail_output("egg", 3, Some(log)), "egg");
+    assert_eq!(tail_output("lorem ipsum egg", 3, None), "egg");
+}
+
+#[test]
+fn cancelled_tasks_are_reported_as_failed() {
+    let reason = "the job was cancelled by SIGTERM".to_string();
+    assert_eq!(to_task_run_state(&State::Cancelled(reason.clone())),
+               TaskRunState::FAILED);
+
+    let mut task = Task::new("apple", make_task("apple", &vec![]));
+    task.state = State::Cancelled(reason);
+    assert_eq!(to_job_run_state(&ExecutionState::Finished, &vec![task]),
+               JobRunState::FAILED);
+}
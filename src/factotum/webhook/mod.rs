@@ -13,28 +13,308 @@
 //
 
 pub mod jobcontext;
-mod jobupdate;
+pub mod jobupdate;
+pub mod sink;
+pub mod snowplow;
+pub mod spool;
 #[cfg(test)]
 mod tests;
 
 
 use std::thread;
 use std::thread::JoinHandle;
-use std::sync::mpsc::Receiver;
-use factotum::executor::{ExecutionState, ExecutionUpdate};
-use std::time::Duration;
+use std::sync::mpsc::{Receiver, Sender};
+use factotum::executor::{ExecutionState, ExecutionUpdate, Transition};
+use factotum::executor::task_list::State;
+use factotum::cancellation;
+use std::time::{Duration, Instant};
 use rand;
 use factotum::webhook::jobcontext::JobContext;
 use std::collections::HashMap;
+use std::path::Path;
+use chrono::UTC;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json::{self, Json};
+use std::cmp;
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use hyper;
+use hyper::net::{HttpConnector, HttpStream, NetworkConnector};
 
-const MAX_RETRIES: usize = 3;
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
 
+pub const SIGNATURE_HEADER: &'static str = "X-Factotum-Signature";
+pub const TIMESTAMP_HEADER: &'static str = "X-Factotum-Timestamp";
+
+pub fn hmac_sha256_hex(secret: &str, message: &str) -> String {
+    let mut hmac = Hmac::new(Sha256::new(), secret.as_bytes());
+    hmac.input(message.as_bytes());
+    hmac.result().code().to_hex()
+}
+
+// the timestamp is signed along with the body ("<timestamp>.<body>") so replays can be rejected
+pub fn signature_headers(secret: &str, timestamp: i64, body: &str) -> Vec<(String, String)> {
+    let signed_content = format!("{}.{}", timestamp, body);
+    vec![(TIMESTAMP_HEADER.to_string(), timestamp.to_string()),
+         (SIGNATURE_HEADER.to_string(),
+          format!("sha256={}", hmac_sha256_hex(secret, &signed_content)))]
+}
+
+// the headers to send with a request, signed at the time it's made if there's a secret
+pub fn get_request_headers(headers: &[(String, String)],
+                           secret: &Option<String>,
+                           body: &str)
+                           -> Vec<(String, String)> {
+    let mut request_headers = headers.to_vec();
+    if let Some(ref s) = *secret {
+        request_headers.extend(signature_headers(s, UTC::now().timestamp(), body));
+    }
+    request_headers
+}
+
+#[allow(dead_code)]
 pub fn backoff_rand_1_minute() -> Duration {
-    let max_duration_millis = 60 * 1000;
+    backoff_rand(Duration::from_secs(60))
+}
+
+pub fn backoff_rand(max: Duration) -> Duration {
+    let max_duration_millis = max.as_secs() * 1000 + (max.subsec_nanos() / 1_000_000) as u64;
+    if max_duration_millis == 0 {
+        return Duration::from_millis(0);
+    }
     let random_ms = rand::random::<u64>();
     Duration::from_millis(random_ms % max_duration_millis)
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backoff {
+    Random,
+    Exponential,
+    Fixed,
+}
+
+impl Backoff {
+    pub fn from_name(name: &str) -> Result<Backoff, String> {
+        match name {
+            "random" => Ok(Backoff::Random),
+            "exponential" => Ok(Backoff::Exponential),
+            "fixed" => Ok(Backoff::Fixed),
+            _ => {
+                Err(format!("unknown webhook backoff '{}' (expected random, exponential or fixed)",
+                            name))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            backoff: Backoff::Random,
+            delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // how long to wait after the given (failed) attempt before the next - the first attempt is 1
+    // random waits anywhere up to the delay, exponential doubles the delay after each attempt
+    pub fn delay_after_attempt(&self, attempt: u32) -> Duration {
+        match self.backoff {
+            Backoff::Random => backoff_rand(self.delay),
+            Backoff::Fixed => self.delay,
+            Backoff::Exponential => {
+                let doublings = cmp::min(attempt.saturating_sub(1), 16);
+                self.delay * 2u32.pow(doublings)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+    Job,
+    Task,
+    TaskFailure,
+}
+
+// which updates get sent to a webhook
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventFilter {
+    All,
+    Only(Vec<EventKind>),
+}
+
+impl EventFilter {
+    // a comma separated list of all, job, task and task-failures
+    pub fn from_names(names: &str) -> Result<EventFilter, String> {
+        let mut kinds = vec![];
+        for name in names.split(',').map(|n| n.trim()) {
+            match name {
+                "all" => return Ok(EventFilter::All),
+                "job" => kinds.push(EventKind::Job),
+                "task" => kinds.push(EventKind::Task),
+                "task-failures" => kinds.push(EventKind::TaskFailure),
+                _ => {
+                    return Err(format!("unknown webhook event '{}' (expected all, job, task or \
+                                        task-failures)",
+                                       name))
+                }
+            }
+        }
+        Ok(EventFilter::Only(kinds))
+    }
+
+    pub fn matches(&self, update: &ExecutionUpdate) -> bool {
+        match *self {
+            EventFilter::All => true,
+            EventFilter::Only(ref kinds) => {
+                kinds.iter().any(|kind| {
+                    match (kind, &update.transition) {
+                        (&EventKind::Job, &Transition::Job(_)) => true,
+                        (&EventKind::Task, &Transition::Task(_)) => true,
+                        (&EventKind::TaskFailure, &Transition::Task(ref transitions)) => {
+                            transitions.iter().any(|t| match t.to_state {
+                                State::Failed(_) | State::Cancelled(_) => true,
+                                _ => false,
+                            })
+                        }
+                        _ => false,
+                    }
+                })
+            }
+        }
+    }
+}
+
+impl Default for EventFilter {
+    fn default() -> Self {
+        EventFilter::All
+    }
+}
+
+// updates are sent together once there are `size` of them, or the first has waited `interval`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batching {
+    pub size: usize,
+    pub interval: Option<Duration>,
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Batching {
+            size: 1,
+            interval: None,
+        }
+    }
+}
+
+impl Batching {
+    pub fn is_enabled(&self) -> bool {
+        self.size > 1 || self.interval.is_some()
+    }
+
+    pub fn is_due(&self, batched: usize, started: Instant) -> bool {
+        batched >= self.size || self.interval.map_or(false, |i| started.elapsed() >= i)
+    }
+}
+
+// a batch is sent as a JSON array of the self-describing updates
+pub fn as_batch(updates: &[String]) -> String {
+    format!("[{}]", updates.join(","))
+}
+
+// the updates in something built by as_batch (or just the update, if it isn't a batch)
+pub fn split_batch(data: &str) -> Vec<String> {
+    match Json::from_str(data) {
+        Ok(Json::Array(updates)) => updates.iter().map(|u| json::encode(u).unwrap()).collect(),
+        _ => vec![data.to_string()],
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HttpTimeouts {
+    pub connect: Option<Duration>,
+    pub read: Option<Duration>,
+}
+
+// hyper's own connector can't give up on a slow connect, so this one does it with connect_timeout
+struct TimeoutConnector {
+    timeout: Option<Duration>,
+}
+
+impl NetworkConnector for TimeoutConnector {
+    type Stream = HttpStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<HttpStream> {
+        let timeout = match self.timeout {
+            Some(t) => t,
+            None => return HttpConnector.connect(host, port, scheme),
+        };
+
+        let mut last_err = io::Error::new(io::ErrorKind::Other,
+                                          format!("couldn't resolve '{}'", host));
+        for addr in try!((host, port).to_socket_addrs()) {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => return Ok(HttpStream(stream)),
+                Err(e) => last_err = e,
+            }
+        }
+        Err(hyper::Error::from(last_err))
+    }
+}
+
+// parses a "Name: value" header as given on the command line
+pub fn parse_header(header: &str) -> Result<(String, String), String> {
+    let mut split = header.splitn(2, ':');
+    let name = split.next().unwrap_or("").trim();
+    let value = match split.next() {
+        Some(v) => v.trim(),
+        None => return Err(format!("the header '{}' must be given as \"Name: value\"", header)),
+    };
+
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("the header '{}' doesn't have a valid name", header));
+    }
+
+    Ok((name.to_string(), value.to_string()))
+}
+
+// copies every update to each of the subscribers (one per webhook endpoint), finishing once the
+// sender of the updates has gone away
+pub fn fan_out(updates_channel: Receiver<ExecutionUpdate>,
+               subscribers: Vec<Sender<ExecutionUpdate>>)
+               -> JoinHandle<()> {
+    thread::spawn(move || {
+        for update in updates_channel.iter() {
+            for subscriber in subscribers.iter() {
+                if subscriber.send(update.clone()).is_err() {
+                    warn!("A webhook stopped listening for job updates");
+                }
+            }
+        }
+    })
+}
+
+// when there are several endpoints each gets its own spool (so a flush only replays its events)
+pub fn get_endpoint_spool_dir(spool_dir: &str, endpoint: &str) -> String {
+    let endpoint_dir: String = endpoint.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    Path::new(spool_dir).join(endpoint_dir).display().to_string()
+}
+
 #[derive(Debug,Clone,PartialEq)]
 pub struct Attempt {
     code: Option<u32>,
@@ -62,6 +342,9 @@ pub struct WebhookResult {
     pub events_received: u32,
     pub failed_count: u32,
     pub success_count: u32,
+    pub spooled_count: u32,
+    pub undelivered_count: u32,
+    pub filtered_count: u32,
     pub results: Vec<WebhookAttemptResult>,
 }
 
@@ -75,6 +358,9 @@ impl WebhookResult {
             events_received: events_received,
             failed_count: fail_count,
             success_count: success_count,
+            spooled_count: 0,
+            undelivered_count: 0,
+            filtered_count: 0,
             results: results,
         }
     }
@@ -84,12 +370,22 @@ pub struct Webhook {
     pub factfile_job_name: String,
     pub factfile_json: String,
     pub endpoint: String,
+    pub headers: Vec<(String, String)>,
+    secret: Option<String>,
+    pub spool_dir: Option<String>,
+    pub max_attempts: u32,
+    pub event_filter: EventFilter,
+    pub batching: Batching,
     job_context: JobContext,
     pub max_stdouterr_size: usize,
 }
 
 impl Webhook {
-    pub fn http_post(url: &str, data: &str) -> Result<u32, (u32, String)> {
+    pub fn http_post(url: &str,
+                     extra_headers: &[(String, String)],
+                     data: &str,
+                     timeouts: &HttpTimeouts)
+                     -> Result<u32, (u32, String)> {
         use hyper::Client;
         use hyper::net::HttpsConnector;
         use hyper_native_tls::NativeTlsClient;
@@ -98,12 +394,20 @@ impl Webhook {
         use hyper::status;
 
         let ssl = NativeTlsClient::new().unwrap();
-        let connector = HttpsConnector::new(ssl);
-        let client = Client::with_connector(connector);
+        let connector = HttpsConnector::with_connector(ssl,
+                                                       TimeoutConnector {
+                                                           timeout: timeouts.connect,
+                                                       });
+        let mut client = Client::with_connector(connector);
+        client.set_read_timeout(timeouts.read);
+        client.set_write_timeout(timeouts.read);
         let mut headers = Headers::new();
         headers.set(ContentType(Mime(TopLevel::Application,
                                      SubLevel::Json,
                                      vec![(Attr::Charset, Value::Utf8)])));
+        for &(ref name, ref value) in extra_headers.iter() {
+            headers.set_raw(name.clone(), vec![value.clone().into_bytes()]);
+        }
 
         let res = client.post(url)
             .headers(headers)
@@ -121,11 +425,31 @@ impl Webhook {
         }
     }
 
+    #[allow(dead_code)]
     pub fn new<S: Into<String>>(factfile_job_name: S, factfile_json: S, endpoint: S, job_tags:Option<HashMap<String,String>>, max_stdouterr_size:Option<usize>) -> Self {
         let ff_name: String = factfile_job_name.into();
         let ff_json: String = factfile_json.into();
-        let jc = jobcontext::JobContext::new(ff_name.clone(), &ff_json, job_tags);
+        let jc = jobcontext::JobContext::new(ff_name, &ff_json, job_tags);
 
+        Webhook::from_job_context(jc,
+                                  ff_json,
+                                  endpoint.into(),
+                                  vec![],
+                                  None,
+                                  None,
+                                  DEFAULT_MAX_ATTEMPTS,
+                                  max_stdouterr_size)
+    }
+
+    pub fn from_job_context<S: Into<String>>(job_context: JobContext,
+                                             factfile_json: S,
+                                             endpoint: S,
+                                             headers: Vec<(String, String)>,
+                                             secret: Option<String>,
+                                             spool_dir: Option<String>,
+                                             max_attempts: u32,
+                                             max_stdouterr_size: Option<usize>)
+                                             -> Self {
         let max_stdouterr_size_bytes: usize = if let Some(max_bytes) = max_stdouterr_size {
             max_bytes
         } else {
@@ -133,10 +457,16 @@ impl Webhook {
         };
 
         Webhook {
-            job_context: jc,
-            factfile_job_name: ff_name,
-            factfile_json: ff_json,
+            factfile_job_name: job_context.job_name.clone(),
+            job_context: job_context,
+            factfile_json: factfile_json.into(),
             endpoint: endpoint.into(),
+            headers: headers,
+            secret: secret,
+            spool_dir: spool_dir,
+            max_attempts: max_attempts,
+            event_filter: EventFilter::All,
+            batching: Batching::default(),
             max_stdouterr_size: max_stdouterr_size_bytes,
         }
     }
@@ -146,11 +476,17 @@ impl Webhook {
                                  emitter_func: F,
                                  backoff_retry_period: G)
                                  -> JoinHandle<WebhookResult>
-        where F: Fn(&str, &str) -> Result<u32, (u32, String)> + Send + Sync + 'static + Copy,
-              G: Fn() -> Duration + Send + Sync + 'static
+        where F: Fn(&str, &[(String, String)], &str) -> Result<u32, (u32, String)> + Send + Sync + 'static + Copy,
+              G: Fn(u32) -> Duration + Send + Sync + 'static
     {
 
         let endpoint = self.endpoint.clone();
+        let headers = self.headers.clone();
+        let secret = self.secret.clone();
+        let spool_dir = self.spool_dir.clone();
+        let max_attempts = self.max_attempts;
+        let event_filter = self.event_filter.clone();
+        let batching = self.batching.clone();
         let job_context = self.job_context.clone();
         let max_stdouterr_size = self.max_stdouterr_size.clone();
 
@@ -159,25 +495,73 @@ impl Webhook {
             let mut attempts = vec![];
             let mut fail_count = 0;
             let mut success_count = 0;
+            let mut spooled_count = 0;
+            let mut undelivered_count = 0;
+            let mut filtered_count = 0;
             let mut done = false;
             let mut events_recv = 0;
+            let mut batch: Vec<(ExecutionUpdate, String)> = vec![];
+            let mut batch_started = Instant::now();
 
             while done == false {
 
-                let message: ExecutionUpdate = updates_channel.recv().unwrap();
-                events_recv += 1;
+                // once a batch has been started, only wait for more updates until it's due
+                let received = match batching.interval {
+                    Some(interval) if !batch.is_empty() => {
+                        let waited = batch_started.elapsed();
+                        let remaining = if interval > waited {
+                            interval - waited
+                        } else {
+                            Duration::from_secs(0)
+                        };
+                        updates_channel.recv_timeout(remaining).ok()
+                    }
+                    _ => Some(updates_channel.recv().unwrap()),
+                };
+
+                if let Some(message) = received {
+                    events_recv += 1;
 
-                if ExecutionState::Finished == message.execution_state {
-                    done = true;
+                    if ExecutionState::Finished == message.execution_state {
+                        done = true;
+                    }
+
+                    if event_filter.matches(&message) {
+                        let job_update = jobupdate::JobUpdate::new(&job_context,
+                                                                   &message,
+                                                                   &max_stdouterr_size);
+                        if batch.is_empty() {
+                            batch_started = Instant::now();
+                        }
+                        batch.push((message, job_update.as_self_desc_json()));
+                    } else {
+                        filtered_count += 1;
+                    }
                 }
 
-                let job_update = jobupdate::JobUpdate::new(&job_context, &message, &max_stdouterr_size);
-                let json_post_data = job_update.as_self_desc_json();
+                if batch.is_empty() || !(done || batching.is_due(batch.len(), batch_started)) {
+                    continue;
+                }
 
-                for _ in 0..MAX_RETRIES {
+                let message = batch.last().unwrap().0.clone();
+                let json_post_data = if batching.is_enabled() {
+                    as_batch(&batch.iter().map(|b| b.1.clone()).collect::<Vec<String>>())
+                } else {
+                    batch[0].1.clone()
+                };
+                let batch_len = batch.len() as u32;
+                batch.clear();
+
+                let mut delivered = false;
+
+                for attempt in 1..(max_attempts + 1) {
                     let mut good = false;
 
-                    let attempt = match emitter_func(&endpoint, &json_post_data) {
+                    let request_headers = get_request_headers(&headers, &secret, &json_post_data);
+
+                    let attempt_result = match emitter_func(&endpoint,
+                                                            &request_headers,
+                                                            &json_post_data) {
                         Ok(code) => {
                             success_count = success_count + 1;
                             good = true;
@@ -193,17 +577,43 @@ impl Webhook {
                         }
                     };
 
-                    attempts.push(attempt);
+                    attempts.push(attempt_result);
 
                     if good {
+                        delivered = true;
                         break;
-                    } else {
-                        thread::sleep(backoff_retry_period());
+                    } else if attempt < max_attempts &&
+                              !cancellation::sleep_unless_cancelled(backoff_retry_period(attempt)) {
+                        // the user is waiting for factotum to exit, so the update is spooled (or
+                        // dropped) rather than retried
+                        info!("Not retrying the webhook update to '{}' as the job was cancelled",
+                              &endpoint);
+                        break;
+                    }
+                }
+
+                if !delivered {
+                    undelivered_count += batch_len;
+                    if let Some(ref dir) = spool_dir {
+                        match spool::spool_event(dir,
+                                                 &job_context.run_reference,
+                                                 events_recv,
+                                                 &json_post_data) {
+                            Ok(filename) => {
+                                spooled_count += batch_len;
+                                info!("Spooled undelivered webhook update to '{}'", filename);
+                            }
+                            Err(msg) => warn!("Couldn't spool webhook update: {}", msg),
+                        }
                     }
                 }
             }
 
-            WebhookResult::new(events_recv, fail_count, success_count, attempts)
+            let mut result = WebhookResult::new(events_recv, fail_count, success_count, attempts);
+            result.spooled_count = spooled_count;
+            result.undelivered_count = undelivered_count;
+            result.filtered_count = filtered_count;
+            result
         })
     }
 }
@@ -0,0 +1,123 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+#[cfg(test)]
+mod tests;
+
+use factotum::webhook::{self, HttpTimeouts, Webhook};
+use factotum::webhook::snowplow;
+use hyper::Url;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+// where job updates get sent, picked by the scheme of the URL given with --webhook
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sink {
+    Http(String),
+    Snowplow(String),
+    File(PathBuf),
+    Stdout,
+    Stderr,
+}
+
+impl Sink {
+    pub fn from_url(url: &str) -> Result<Sink, String> {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            match Url::parse(url) {
+                Ok(_) => Ok(Sink::Http(url.to_string())),
+                Err(msg) => Err(format!("{}", msg)),
+            }
+        } else if url.starts_with(snowplow::SNOWPLOW_SCHEME) ||
+                  url.starts_with(snowplow::SNOWPLOW_HTTP_SCHEME) {
+            snowplow::get_collector_endpoint(url).map(Sink::Snowplow)
+        } else if url.starts_with("file://") {
+            match Url::parse(url).ok().and_then(|u| u.to_file_path().ok()) {
+                Some(path) => Ok(Sink::File(path)),
+                None => {
+                    Err("file URLs must give an absolute path, like \
+                         'file:///var/log/factotum/events.jsonl'"
+                        .into())
+                }
+            }
+        } else if url == "stdout://" {
+            Ok(Sink::Stdout)
+        } else if url == "stderr://" {
+            Ok(Sink::Stderr)
+        } else {
+            Err("URL must begin with 'http://', 'https://', 'snowplow://', 'snowplow+http://', \
+                 'file://', 'stdout://' or 'stderr://' to be used with Factotum webhooks"
+                .into())
+        }
+    }
+
+    // headers and timeouts only make sense for HTTP, so the other sinks ignore them
+    pub fn send(&self,
+                headers: &[(String, String)],
+                data: &str,
+                timeouts: &HttpTimeouts)
+                -> Result<u32, (u32, String)> {
+        match *self {
+            Sink::Http(ref url) => Webhook::http_post(url, headers, data, timeouts),
+            Sink::Snowplow(ref endpoint) => {
+                // any signature headers are for the update itself rather than the tp2 wrapper
+                match snowplow::as_new_tp2_payload(data) {
+                    Ok(payload) => Webhook::http_post(endpoint, headers, &payload, timeouts),
+                    Err(msg) => Err((0, msg)),
+                }
+            }
+            Sink::File(ref path) => {
+                OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(path)
+                    .and_then(|mut f| write_lines(&mut f, data))
+                    .map(|_| 0)
+                    .map_err(|e| (0, format!("couldn't append to '{}' ({})", path.display(), e)))
+            }
+            Sink::Stdout => {
+                write_lines(&mut io::stdout(), data)
+                    .map(|_| 0)
+                    .map_err(|e| (0, format!("{}", e)))
+            }
+            Sink::Stderr => {
+                write_lines(&mut io::stderr(), data)
+                    .map(|_| 0)
+                    .map_err(|e| (0, format!("{}", e)))
+            }
+        }
+    }
+}
+
+// one line per update, so batches are split back up
+fn write_lines<W: Write>(out: &mut W, data: &str) -> io::Result<()> {
+    let lines: String = webhook::split_batch(data)
+        .iter()
+        .map(|update| format!("{}\n", update))
+        .collect();
+    out.write_all(lines.as_bytes())
+}
+
+// sends to whichever sink the URL names (wrapped in a closure, this is the emitter given to
+// Webhook::connect_webhook)
+pub fn emit(url: &str,
+            headers: &[(String, String)],
+            data: &str,
+            timeouts: &HttpTimeouts)
+            -> Result<u32, (u32, String)> {
+    match Sink::from_url(url) {
+        Ok(sink) => sink.send(headers, data, timeouts),
+        Err(msg) => Err((0, msg)),
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use super::*;
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+
+#[test]
+fn http_urls_good() {
+    assert_eq!(Sink::from_url("https://potato.com/"),
+               Ok(Sink::Http("https://potato.com/".to_string())));
+    assert_eq!(Sink::from_url("http://"), Err("empty host".to_string()));
+}
+
+#[test]
+fn file_urls_need_absolute_path() {
+    assert_eq!(Sink::from_url("file:///var/log/factotum/events.jsonl"),
+               Ok(Sink::File(PathBuf::from("/var/log/factotum/events.jsonl"))));
+    assert_eq!(Sink::from_url("file://events.jsonl"),
+               Err("file URLs must give an absolute path, like \
+                    'file:///var/log/factotum/events.jsonl'"
+                   .to_string()));
+}
+
+#[test]
+fn snowplow_urls_good() {
+    assert_eq!(Sink::from_url("snowplow://collector.acme.com"),
+               Ok(Sink::Snowplow("https://collector.acme.com/com.snowplowanalytics.snowplow/tp2"
+                   .to_string())));
+    assert!(Sink::from_url("snowplow://collector.acme.com/i").is_err());
+}
+
+#[test]
+fn std_streams_good() {
+    assert_eq!(Sink::from_url("stdout://"), Ok(Sink::Stdout));
+    assert_eq!(Sink::from_url("stderr://"), Ok(Sink::Stderr));
+}
+
+#[test]
+fn unknown_schemes_rejected() {
+    assert_eq!(Sink::from_url("potato.com/"),
+               Err("URL must begin with 'http://', 'https://', 'snowplow://', \
+                    'snowplow+http://', 'file://', 'stdout://' or 'stderr://' to be used with \
+                    Factotum webhooks"
+                   .to_string()));
+    assert!(Sink::from_url("ftp://potato.com/").is_err());
+}
+
+#[test]
+fn file_sink_appends_lines() {
+    let mut path = env::temp_dir();
+    path.push("factotum-sink-test.jsonl");
+    fs::remove_file(&path).ok();
+    let sink = Sink::File(path.clone());
+
+    assert_eq!(sink.send(&[], r#"{"a":1}"#, &HttpTimeouts::default()), Ok(0));
+    assert_eq!(sink.send(&[], r#"{"b":2}"#, &HttpTimeouts::default()), Ok(0));
+
+    let mut contents = String::new();
+    File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+    fs::remove_file(&path).ok();
+
+    assert_eq!(contents, "{\"a\":1}\n{\"b\":2}\n");
+}
+
+#[test]
+fn file_sink_splits_batches() {
+    let mut path = env::temp_dir();
+    path.push("factotum-sink-batch-test.jsonl");
+    fs::remove_file(&path).ok();
+    let sink = Sink::File(path.clone());
+    let batch = webhook::as_batch(&[r#"{"a":1}"#.to_string(), r#"{"b":2}"#.to_string()]);
+
+    assert_eq!(sink.send(&[], &batch, &HttpTimeouts::default()), Ok(0));
+
+    let mut contents = String::new();
+    File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+    fs::remove_file(&path).ok();
+
+    assert_eq!(contents, "{\"a\":1}\n{\"b\":2}\n");
+}
+
+#[test]
+fn emit_rejects_bad_urls() {
+    assert!(emit("potato.com/", &[], "{}", &HttpTimeouts::default()).is_err());
+}
@@ -0,0 +1,112 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+#[cfg(test)]
+mod tests;
+
+use chrono::{Timelike, UTC};
+use hyper::Url;
+use rustc_serialize::base64::{ToBase64, URL_SAFE};
+use rustc_serialize::json::{self, Json, ToJson};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use factotum::webhook;
+
+pub const SNOWPLOW_SCHEME: &'static str = "snowplow://";
+pub const SNOWPLOW_HTTP_SCHEME: &'static str = "snowplow+http://";
+
+const TP2_PATH: &'static str = "com.snowplowanalytics.snowplow/tp2";
+const PAYLOAD_DATA_SCHEMA: &'static str = "iglu:com.snowplowanalytics.snowplow/payload_data/\
+                                           jsonschema/1-0-4";
+const UNSTRUCT_EVENT_SCHEMA: &'static str = "iglu:com.snowplowanalytics.snowplow/unstruct_event/\
+                                             jsonschema/1-0-0";
+const APP_ID: &'static str = "factotum";
+
+// turns snowplow://collector.acme.com (or snowplow+http:// for a collector without TLS) into
+// the collector's tp2 endpoint
+pub fn get_collector_endpoint(url: &str) -> Result<String, String> {
+    let (scheme, collector) = if url.starts_with(SNOWPLOW_SCHEME) {
+        ("https", &url[SNOWPLOW_SCHEME.len()..])
+    } else if url.starts_with(SNOWPLOW_HTTP_SCHEME) {
+        ("http", &url[SNOWPLOW_HTTP_SCHEME.len()..])
+    } else {
+        return Err(format!("'{}' isn't a snowplow collector URL", url));
+    };
+
+    let collector = collector.trim_right_matches('/');
+    if collector.is_empty() || collector.contains('/') {
+        return Err("snowplow URLs should only give the collector's host (and port), like \
+                    'snowplow://collector.acme.com'"
+            .into());
+    }
+
+    let endpoint = format!("{}://{}/{}", scheme, collector, TP2_PATH);
+    match Url::parse(&endpoint) {
+        Ok(_) => Ok(endpoint),
+        Err(msg) => Err(format!("{}", msg)),
+    }
+}
+
+// wraps self-describing job/task updates as unstructured events in a tp2 POST payload
+pub fn as_tp2_payload<F>(self_desc_jsons: &[String],
+                         timestamp_ms: i64,
+                         new_event_id: F)
+                         -> Result<String, String>
+    where F: Fn() -> String
+{
+    let mut events = vec![];
+    for self_desc_json in self_desc_jsons.iter() {
+        events.push(try!(as_tp2_event(self_desc_json, &new_event_id(), timestamp_ms)));
+    }
+
+    let mut payload = BTreeMap::new();
+    payload.insert("schema".to_string(), PAYLOAD_DATA_SCHEMA.to_json());
+    payload.insert("data".to_string(), Json::Array(events));
+    Ok(json::encode(&Json::Object(payload)).unwrap())
+}
+
+fn as_tp2_event(self_desc_json: &str, event_id: &str, timestamp_ms: i64) -> Result<Json, String> {
+    let event = try!(Json::from_str(self_desc_json)
+        .map_err(|e| format!("the update isn't valid JSON: {}", e)));
+
+    let mut unstruct_event = BTreeMap::new();
+    unstruct_event.insert("schema".to_string(), UNSTRUCT_EVENT_SCHEMA.to_json());
+    unstruct_event.insert("data".to_string(), event);
+    let ue_px = json::encode(&Json::Object(unstruct_event))
+        .unwrap()
+        .as_bytes()
+        .to_base64(URL_SAFE);
+
+    let timestamp = timestamp_ms.to_string();
+    let mut fields = BTreeMap::new();
+    fields.insert("e".to_string(), "ue".to_json());
+    fields.insert("p".to_string(), "srv".to_json());
+    fields.insert("tv".to_string(),
+                  format!("factotum-{}", env!("CARGO_PKG_VERSION")).to_json());
+    fields.insert("aid".to_string(), APP_ID.to_json());
+    fields.insert("eid".to_string(), event_id.to_json());
+    fields.insert("dtm".to_string(), timestamp.to_json());
+    fields.insert("stm".to_string(), timestamp.to_json());
+    fields.insert("ue_px".to_string(), ue_px.to_json());
+    Ok(Json::Object(fields))
+}
+
+// takes a single update, or a batch of them
+pub fn as_new_tp2_payload(data: &str) -> Result<String, String> {
+    let now = UTC::now();
+    let timestamp_ms = now.timestamp() * 1000 + (now.nanosecond() / 1_000_000) as i64;
+    as_tp2_payload(&webhook::split_batch(data),
+                   timestamp_ms,
+                   || Uuid::new_v4().to_string())
+}
@@ -0,0 +1,79 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use super::*;
+use rustc_serialize::base64::FromBase64;
+
+#[test]
+fn collector_endpoints() {
+    assert_eq!(get_collector_endpoint("snowplow://collector.acme.com"),
+               Ok("https://collector.acme.com/com.snowplowanalytics.snowplow/tp2".to_string()));
+    assert_eq!(get_collector_endpoint("snowplow+http://localhost:8080/"),
+               Ok("http://localhost:8080/com.snowplowanalytics.snowplow/tp2".to_string()));
+}
+
+#[test]
+fn collector_endpoints_bad() {
+    let host_only = Err("snowplow URLs should only give the collector's host (and port), like \
+                         'snowplow://collector.acme.com'"
+        .to_string());
+    assert_eq!(get_collector_endpoint("snowplow://"), host_only);
+    assert_eq!(get_collector_endpoint("snowplow://collector.acme.com/i"), host_only);
+    assert_eq!(get_collector_endpoint("https://collector.acme.com"),
+               Err("'https://collector.acme.com' isn't a snowplow collector URL".to_string()));
+}
+
+#[test]
+fn tp2_payload_wraps_update() {
+    let update = "{\"schema\":\"iglu:com.snowplowanalytics.factotum/job_update/jsonschema/1-0-0\",\
+                  \"data\":{\"a\":1}}";
+
+    let payload = Json::from_str(&as_tp2_payload(&[update.to_string()],
+                                                 1500000000123,
+                                                 || "some-uuid".to_string())
+            .unwrap())
+        .unwrap();
+
+    assert_eq!(payload.find("schema").unwrap().as_string().unwrap(),
+               PAYLOAD_DATA_SCHEMA);
+    let event = &payload.find("data").unwrap().as_array().unwrap()[0];
+    assert_eq!(event.find("e").unwrap().as_string(), Some("ue"));
+    assert_eq!(event.find("p").unwrap().as_string(), Some("srv"));
+    assert_eq!(event.find("aid").unwrap().as_string(), Some("factotum"));
+    assert_eq!(event.find("eid").unwrap().as_string(), Some("some-uuid"));
+    assert_eq!(event.find("dtm").unwrap().as_string(), Some("1500000000123"));
+
+    let ue_px = event.find("ue_px").unwrap().as_string().unwrap().from_base64().unwrap();
+    let unstruct_event = Json::from_str(&String::from_utf8(ue_px).unwrap()).unwrap();
+    assert_eq!(unstruct_event.find("schema").unwrap().as_string(),
+               Some(UNSTRUCT_EVENT_SCHEMA));
+    assert_eq!(unstruct_event.find("data").unwrap(),
+               &Json::from_str(update).unwrap());
+}
+
+#[test]
+fn tp2_payload_needs_json() {
+    assert!(as_tp2_payload(&["{".to_string()], 0, || "some-uuid".to_string()).is_err());
+}
+
+#[test]
+fn tp2_payload_from_batch() {
+    let batch = webhook::as_batch(&["{\"a\":1}".to_string(), "{\"b\":2}".to_string()]);
+
+    let payload = Json::from_str(&as_new_tp2_payload(&batch).unwrap()).unwrap();
+
+    let events = payload.find("data").unwrap().as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].find("eid") != events[1].find("eid"));
+}
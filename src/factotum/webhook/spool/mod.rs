@@ -0,0 +1,124 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+#[cfg(test)]
+mod tests;
+
+use chrono::UTC;
+use factotum::webhook::get_request_headers;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+const SPOOL_EXTENSION: &'static str = "json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlushResult {
+    pub sent: u32,
+    pub remaining: u32,
+    pub failure: Option<String>,
+}
+
+// writes an undelivered (self-describing JSON) event to the spool, returning the file written
+// files are named so that sorting them by name gives the order the events happened in
+pub fn spool_event(spool_dir: &str,
+                   run_reference: &str,
+                   sequence: u32,
+                   event: &str)
+                   -> Result<String, String> {
+    try!(fs::create_dir_all(spool_dir)
+        .map_err(|e| format!("couldn't create directory '{}' ({})", spool_dir, e)));
+
+    let filename = format!("{}-{}-{:06}.{}",
+                           UTC::now().format("%Y%m%dT%H%M%S%.6f"),
+                           run_reference,
+                           sequence,
+                           SPOOL_EXTENSION);
+    let path = Path::new(spool_dir).join(filename);
+
+    let mut fh = try!(File::create(&path)
+        .map_err(|e| format!("couldn't create file '{}' ({})", path.display(), e)));
+    try!(fh.write_all(event.as_bytes())
+        .map_err(|e| format!("couldn't write to file '{}' ({})", path.display(), e)));
+
+    Ok(path.display().to_string())
+}
+
+pub fn get_spooled_events(spool_dir: &str) -> Result<Vec<PathBuf>, String> {
+    let entries = try!(fs::read_dir(spool_dir)
+        .map_err(|e| format!("couldn't read the spool directory '{}' ({})", spool_dir, e)));
+
+    let mut events = vec![];
+    for entry in entries {
+        let path = try!(entry.map_err(|e| {
+                format!("couldn't read the spool directory '{}' ({})", spool_dir, e)
+            }))
+            .path();
+        if path.is_file() && path.extension().map_or(false, |ext| ext == SPOOL_EXTENSION) {
+            events.push(path);
+        }
+    }
+
+    events.sort();
+    Ok(events)
+}
+
+// sends the spooled events in order, deleting each once it's been accepted
+// stops at the first failure so that events are never delivered out of order
+pub fn flush<F>(spool_dir: &str,
+                endpoint: &str,
+                headers: &[(String, String)],
+                secret: &Option<String>,
+                emitter_func: F)
+                -> Result<FlushResult, String>
+    where F: Fn(&str, &[(String, String)], &str) -> Result<u32, (u32, String)>
+{
+    let events = try!(get_spooled_events(spool_dir));
+    let total = events.len() as u32;
+    let mut sent = 0;
+
+    for path in events.iter() {
+        let mut event = String::new();
+        try!(File::open(path)
+            .and_then(|mut fh| fh.read_to_string(&mut event))
+            .map_err(|e| format!("couldn't read '{}' ({})", path.display(), e)));
+
+        let request_headers = get_request_headers(headers, secret, &event);
+        if let Err((code, reason)) = emitter_func(endpoint, &request_headers, &event) {
+            warn!("Failed to send spooled webhook event '{}' to '{}': {}, {}",
+                  path.display(),
+                  endpoint,
+                  code,
+                  reason);
+            return Ok(FlushResult {
+                sent: sent,
+                remaining: total - sent,
+                failure: Some(format!("couldn't send '{}' ({}, {})", path.display(), code, reason)),
+            });
+        }
+
+        try!(fs::remove_file(path).map_err(|e| {
+            format!("'{}' was sent but couldn't be removed from the spool ({})",
+                    path.display(),
+                    e)
+        }));
+        sent += 1;
+    }
+
+    Ok(FlushResult {
+        sent: sent,
+        remaining: 0,
+        failure: None,
+    })
+}
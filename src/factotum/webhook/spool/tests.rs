@@ -0,0 +1,114 @@
+// Copyright (c) 2016-2021 Snowplow Analytics Ltd. All rights reserved.
+//
+// This program is licensed to you under the Apache License Version 2.0, and
+// you may not use this file except in compliance with the Apache License
+// Version 2.0.  You may obtain a copy of the Apache License Version 2.0 at
+// http://www.apache.org/licenses/LICENSE-2.0.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the Apache License Version 2.0 is distributed on an "AS
+// IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.  See the Apache License Version 2.0 for the specific language
+// governing permissions and limitations there under.
+//
+
+use super::*;
+use std::env;
+
+fn make_spool_dir(name: &str) -> String {
+    let mut dir = env::temp_dir();
+    dir.push(format!("factotum-spool-test-{}", name));
+    fs::remove_dir_all(&dir).ok();
+    dir.display().to_string()
+}
+
+fn mock_200_ok(_: &str, _: &[(String, String)], _: &str) -> Result<u32, (u32, String)> {
+    Ok(200)
+}
+
+fn mock_500_err(_: &str, _: &[(String, String)], _: &str) -> Result<u32, (u32, String)> {
+    Err((500, "Internal Server Error".to_string()))
+}
+
+fn mock_reject_second(_: &str, _: &[(String, String)], body: &str) -> Result<u32, (u32, String)> {
+    if body == "second" {
+        Err((503, "Service Unavailable".to_string()))
+    } else {
+        Ok(200)
+    }
+}
+
+fn read_event(path: &PathBuf) -> String {
+    let mut contents = String::new();
+    File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn spooled_events_listed_in_order() {
+    let dir = make_spool_dir("order");
+    spool_event(&dir, "run", 1, "first").unwrap();
+    spool_event(&dir, "run", 2, "second").unwrap();
+    spool_event(&dir, "run", 3, "third").unwrap();
+    File::create(Path::new(&dir).join("notes.txt")).unwrap();
+
+    let events = get_spooled_events(&dir).unwrap();
+
+    assert_eq!(events.iter().map(read_event).collect::<Vec<String>>(),
+               vec!["first", "second", "third"]);
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn missing_spool_dir_is_an_error() {
+    let dir = make_spool_dir("missing");
+    assert!(get_spooled_events(&dir).is_err());
+}
+
+#[test]
+fn flush_sends_and_removes_everything() {
+    let dir = make_spool_dir("flush-all");
+    spool_event(&dir, "run", 1, "first").unwrap();
+    spool_event(&dir, "run", 2, "second").unwrap();
+
+    let result = flush(&dir, "https://goodplace.com", &[], &None, mock_200_ok);
+
+    assert_eq!(result,
+               Ok(FlushResult {
+                   sent: 2,
+                   remaining: 0,
+                   failure: None,
+               }));
+    assert_eq!(get_spooled_events(&dir).unwrap().len(), 0);
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn flush_keeps_events_that_fail() {
+    let dir = make_spool_dir("flush-none");
+    spool_event(&dir, "run", 1, "first").unwrap();
+
+    let result = flush(&dir, "https://goodplace.com", &[], &None, mock_500_err).unwrap();
+
+    assert_eq!(result.sent, 0);
+    assert_eq!(result.remaining, 1);
+    assert!(result.failure.unwrap().contains("500, Internal Server Error"));
+    assert_eq!(get_spooled_events(&dir).unwrap().len(), 1);
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn flush_stops_at_first_failure() {
+    let dir = make_spool_dir("flush-some");
+    spool_event(&dir, "run", 1, "first").unwrap();
+    spool_event(&dir, "run", 2, "second").unwrap();
+    spool_event(&dir, "run", 3, "third").unwrap();
+
+    let result = flush(&dir, "https://goodplace.com", &[], &None, mock_reject_second).unwrap();
+
+    assert_eq!(result.sent, 1);
+    assert_eq!(result.remaining, 2);
+    assert_eq!(get_spooled_events(&dir).unwrap().iter().map(read_event).collect::<Vec<String>>(),
+               vec!["second", "third"]);
+    fs::remove_dir_all(&dir).ok();
+}
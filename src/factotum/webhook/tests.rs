@@ -14,18 +14,54 @@
 
 use super::*;
 use std::sync::mpsc;
-use factotum::executor::{ExecutionState, TaskSnapshot, JobTransition, Transition, ExecutionUpdate};
+use factotum::executor::{ExecutionState, TaskSnapshot, JobTransition, Transition, ExecutionUpdate,
+                         TaskTransition};
 use std::time::Duration;
+use std::path::Path;
+use libc;
 
-fn mock_200_ok(_: &str, _: &str) -> Result<u32, (u32, String)> {
+fn mock_200_ok(_: &str, _: &[(String, String)], _: &str) -> Result<u32, (u32, String)> {
     Ok(200)
 }
 
-fn mock_500_err(_: &str, _: &str) -> Result<u32, (u32, String)> {
+fn mock_500_err(_: &str, _: &[(String, String)], _: &str) -> Result<u32, (u32, String)> {
     Err((500, "Internal Server Error".to_string()))
 }
 
-fn zero_backoff() -> Duration {
+fn mock_requires_bearer(_: &str,
+                        headers: &[(String, String)],
+                        _: &str)
+                        -> Result<u32, (u32, String)> {
+    if headers.iter().any(|&(ref n, ref v)| n == "Authorization" && v == "Bearer abc") {
+        Ok(200)
+    } else {
+        Err((401, "Unauthorized".to_string()))
+    }
+}
+
+fn mock_requires_signature(_: &str,
+                           headers: &[(String, String)],
+                           body: &str)
+                           -> Result<u32, (u32, String)> {
+    let find = |name: &str| {
+        headers.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref v)| v.clone())
+    };
+
+    match (find(TIMESTAMP_HEADER), find(SIGNATURE_HEADER)) {
+        (Some(ts), Some(sig)) => {
+            let signed_content = format!("{}.{}", ts, body);
+            let expected = format!("sha256={}", hmac_sha256_hex("s3cret", &signed_content));
+            if sig == expected {
+                Ok(200)
+            } else {
+                Err((403, "Bad signature".to_string()))
+            }
+        }
+        _ => Err((401, "Unsigned".to_string())),
+    }
+}
+
+fn zero_backoff(_: u32) -> Duration {
     Duration::from_secs(0)
 }
 
@@ -138,19 +174,25 @@ fn failures_tried_three_times() {
              Err(Attempt::new(Some(500), "Internal Server Error", sent_states[3].clone())),
              Err(Attempt::new(Some(500), "Internal Server Error", sent_states[3].clone()))];
 
-    let result = jh.join();
+    let result = jh.join().ok().unwrap();
 
-    assert_eq!(result.ok().unwrap(),
-               WebhookResult::new(sent_states.len() as u32,
-                                  expected_results.len() as u32,
-                                  0,
-                                  expected_results));
+    // with no spool directory every update that ran out of attempts is undelivered
+    assert_eq!(result.undelivered_count, sent_states.len() as u32);
+    assert_eq!(result.spooled_count, 0);
+    assert_eq!(result.filtered_count, 0);
+
+    let mut expected = WebhookResult::new(sent_states.len() as u32,
+                                          expected_results.len() as u32,
+                                          0,
+                                          expected_results);
+    expected.undelivered_count = sent_states.len() as u32;
+    assert_eq!(result, expected);
 }
 
 #[test]
 #[ignore] // this only makes sense if you have ngrok set up
 fn test_webhook_post() {
-    let r = Webhook::http_post("***", r#"{"hello":"world"}"#);
+    let r = Webhook::http_post("***", &[], r#"{"hello":"world"}"#, &HttpTimeouts::default());
 
     match r {
         Ok(code) => assert_eq!(code, 200),
@@ -158,9 +200,60 @@ fn test_webhook_post() {
     }
 }
 
+fn one_minute_backoff(_: u32) -> Duration {
+    Duration::from_secs(60)
+}
+
+fn send_failing_updates_after_cancel() {
+    use std::time::Instant;
+
+    cancellation::request_cancel(libc::SIGTERM);
+
+    let mut wh = Webhook::new("job_name", "hello", "https://goodplace.com", None, None);
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_500_err, one_minute_backoff);
+
+    let started = Instant::now();
+    let sent_states = make_mock_run();
+    for state in sent_states.iter() {
+        tx.send(state.clone()).unwrap();
+    }
+    let result = jh.join().ok().unwrap();
+
+    // every update gets a single attempt, without waiting to retry
+    assert!(started.elapsed() < Duration::from_secs(30));
+    assert_eq!(result.results.len(), sent_states.len());
+    assert_eq!(result.undelivered_count, sent_states.len() as u32);
+}
+
+#[test]
+fn cancelled_jobs_dont_wait_to_retry_updates() {
+    use std::env;
+    use std::process::Command;
+
+    // cancellation is process wide, so the updates are sent from a copy of this test binary
+    if env::var("FACTOTUM_TEST_CANCELLED_WEBHOOK").is_ok() {
+        send_failing_updates_after_cancel();
+        return;
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&["--exact", "factotum::webhook::tests::cancelled_jobs_dont_wait_to_retry_updates"])
+        .env("FACTOTUM_TEST_CANCELLED_WEBHOOK", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("1 passed"), "{}", stdout);
+}
+
 #[test]
 fn bad_urls_post_rejects() {
-    let r = Webhook::http_post("http://****/?", r#"{"hello":"world"}"#);
+    let r = Webhook::http_post("http://****/?",
+                               &[],
+                               r#"{"hello":"world"}"#,
+                               &HttpTimeouts::default());
 
     match r {
         Ok(_) => unreachable!("Test returned good for invalid url"),
@@ -170,3 +263,372 @@ fn bad_urls_post_rejects() {
         }
     }
 }
+
+#[test]
+fn headers_passed_to_emitter() {
+    let jc = jobcontext::JobContext::new("job_name", "hello", None);
+    let headers = vec![("Authorization".to_string(), "Bearer abc".to_string())];
+    let mut wh =
+        Webhook::from_job_context(jc,
+                                  "hello",
+                                  "https://goodplace.com",
+                                  headers,
+                                  None,
+                                  None,
+                                  DEFAULT_MAX_ATTEMPTS,
+                                  None);
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_requires_bearer, zero_backoff);
+    let sent_state =
+        ExecutionUpdate::new(ExecutionState::Finished,
+                             TaskSnapshot::new(),
+                             Transition::Job(JobTransition::new(Some(ExecutionState::Running),
+                                                                ExecutionState::Finished)));
+    tx.send(sent_state.clone()).unwrap();
+    let result = jh.join();
+    assert_eq!(result.ok().unwrap(),
+               WebhookResult::new(1, 0, 1, vec![Ok(Attempt::new(Some(200), "OK", sent_state))]));
+}
+
+#[test]
+fn parse_header_good() {
+    assert_eq!(parse_header("X-Api-Key: abc"),
+               Ok(("X-Api-Key".to_string(), "abc".to_string())));
+    assert_eq!(parse_header("  X-Trace :a:b:c  "),
+               Ok(("X-Trace".to_string(), "a:b:c".to_string())));
+    assert_eq!(parse_header("X-Empty:"),
+               Ok(("X-Empty".to_string(), "".to_string())));
+}
+
+#[test]
+fn parse_header_bad() {
+    assert_eq!(parse_header("X-Api-Key abc"),
+               Err("the header 'X-Api-Key abc' must be given as \"Name: value\"".to_string()));
+    assert_eq!(parse_header(": abc"),
+               Err("the header ': abc' doesn't have a valid name".to_string()));
+    assert_eq!(parse_header("X Api: abc"),
+               Err("the header 'X Api: abc' doesn't have a valid name".to_string()));
+}
+
+#[test]
+fn hmac_sha256_hex_known_value() {
+    assert_eq!(hmac_sha256_hex("key", "The quick brown fox jumps over the lazy dog"),
+               "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+}
+
+#[test]
+fn signature_headers_sign_timestamp_and_body() {
+    let headers = signature_headers("key", 1500000000, "{}");
+    assert_eq!(headers,
+               vec![(TIMESTAMP_HEADER.to_string(), "1500000000".to_string()),
+                    (SIGNATURE_HEADER.to_string(),
+                     format!("sha256={}", hmac_sha256_hex("key", "1500000000.{}")))]);
+    assert!(signature_headers("key", 1500000001, "{}") != headers);
+}
+
+#[test]
+fn secret_signs_each_post() {
+    let jc = jobcontext::JobContext::new("job_name", "hello", None);
+    let mut wh = Webhook::from_job_context(jc,
+                                           "hello",
+                                           "https://goodplace.com",
+                                           vec![],
+                                           Some("s3cret".to_string()),
+                                           None,
+                                           DEFAULT_MAX_ATTEMPTS,
+                                           None);
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_requires_signature, zero_backoff);
+    let sent_state =
+        ExecutionUpdate::new(ExecutionState::Finished,
+                             TaskSnapshot::new(),
+                             Transition::Job(JobTransition::new(Some(ExecutionState::Running),
+                                                                ExecutionState::Finished)));
+    tx.send(sent_state.clone()).unwrap();
+    let result = jh.join();
+    assert_eq!(result.ok().unwrap(),
+               WebhookResult::new(1, 0, 1, vec![Ok(Attempt::new(Some(200), "OK", sent_state))]));
+}
+
+#[test]
+fn undelivered_events_spooled() {
+    use std::env;
+    use std::fs;
+
+    let mut dir = env::temp_dir();
+    dir.push("factotum-webhook-spool-test");
+    fs::remove_dir_all(&dir).ok();
+    let spool_dir = dir.display().to_string();
+
+    let jc = jobcontext::JobContext::new("job_name", "hello", None);
+    let mut wh = Webhook::from_job_context(jc,
+                                           "hello",
+                                           "https://goodplace.com",
+                                           vec![],
+                                           None,
+                                           Some(spool_dir.clone()),
+                                           DEFAULT_MAX_ATTEMPTS,
+                                           None);
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_500_err, zero_backoff);
+
+    for state in make_mock_run().iter() {
+        tx.send(state.clone()).unwrap();
+    }
+
+    let result = jh.join().ok().unwrap();
+    let spooled = spool::get_spooled_events(&spool_dir).unwrap();
+    fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(result.spooled_count, 4);
+    assert_eq!(spooled.len(), 4);
+}
+
+#[test]
+fn fan_out_copies_updates_to_every_subscriber() {
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let (tx_a, rx_a) = mpsc::channel::<ExecutionUpdate>();
+    let (tx_b, rx_b) = mpsc::channel::<ExecutionUpdate>();
+    let jh = fan_out(rx, vec![tx_a, tx_b]);
+
+    let sent_states = make_mock_run();
+    for state in sent_states.iter() {
+        tx.send(state.clone()).unwrap();
+    }
+    drop(tx);
+    jh.join().unwrap();
+
+    assert_eq!(rx_a.iter().collect::<Vec<ExecutionUpdate>>(), sent_states);
+    assert_eq!(rx_b.iter().collect::<Vec<ExecutionUpdate>>(), sent_states);
+}
+
+#[test]
+fn each_endpoint_gets_its_own_result() {
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let (tx_good, rx_good) = mpsc::channel::<ExecutionUpdate>();
+    let (tx_bad, rx_bad) = mpsc::channel::<ExecutionUpdate>();
+    let mut good = Webhook::new("job_name", "hello", "https://goodplace.com", None, None);
+    let mut bad = Webhook::new("job_name", "hello", "https://badplace.com", None, None);
+    let good_jh = good.connect_webhook(rx_good, mock_200_ok, zero_backoff);
+    let bad_jh = bad.connect_webhook(rx_bad, mock_500_err, zero_backoff);
+    fan_out(rx, vec![tx_good, tx_bad]);
+
+    let sent_states = make_mock_run();
+    for state in sent_states.iter() {
+        tx.send(state.clone()).unwrap();
+    }
+
+    let good_result = good_jh.join().ok().unwrap();
+    let bad_result = bad_jh.join().ok().unwrap();
+
+    assert_eq!(good_result.success_count, 4);
+    assert_eq!(good_result.failed_count, 0);
+    assert_eq!(bad_result.success_count, 0);
+    assert_eq!(bad_result.failed_count, 12);
+}
+
+#[test]
+fn endpoint_spool_dirs_differ() {
+    let a = get_endpoint_spool_dir("spool", "https://a.com/collect");
+    let b = get_endpoint_spool_dir("spool", "https://b.com/collect");
+    assert_eq!(a, Path::new("spool").join("https___a_com_collect").display().to_string());
+    assert!(a != b);
+}
+
+#[test]
+fn max_attempts_respected() {
+    let jc = jobcontext::JobContext::new("job_name", "hello", None);
+    let mut wh = Webhook::from_job_context(jc,
+                                           "hello",
+                                           "https://goodplace.com",
+                                           vec![],
+                                           None,
+                                           None,
+                                           5,
+                                           None);
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_500_err, zero_backoff);
+    let sent_state =
+        ExecutionUpdate::new(ExecutionState::Finished,
+                             TaskSnapshot::new(),
+                             Transition::Job(JobTransition::new(Some(ExecutionState::Running),
+                                                                ExecutionState::Finished)));
+    tx.send(sent_state).unwrap();
+    let result = jh.join().ok().unwrap();
+    assert_eq!(result.failed_count, 5);
+    assert_eq!(result.results.len(), 5);
+}
+
+#[test]
+fn backoff_from_name() {
+    assert_eq!(Backoff::from_name("random"), Ok(Backoff::Random));
+    assert_eq!(Backoff::from_name("exponential"), Ok(Backoff::Exponential));
+    assert_eq!(Backoff::from_name("fixed"), Ok(Backoff::Fixed));
+    assert_eq!(Backoff::from_name("linear"),
+               Err("unknown webhook backoff 'linear' (expected random, exponential or fixed)"
+                   .to_string()));
+}
+
+#[test]
+fn retry_policy_delays() {
+    let fixed = RetryPolicy {
+        max_attempts: 3,
+        backoff: Backoff::Fixed,
+        delay: Duration::from_secs(2),
+    };
+    assert_eq!(fixed.delay_after_attempt(1), Duration::from_secs(2));
+    assert_eq!(fixed.delay_after_attempt(4), Duration::from_secs(2));
+
+    let exponential = RetryPolicy { backoff: Backoff::Exponential, ..fixed.clone() };
+    assert_eq!(exponential.delay_after_attempt(1), Duration::from_secs(2));
+    assert_eq!(exponential.delay_after_attempt(2), Duration::from_secs(4));
+    assert_eq!(exponential.delay_after_attempt(3), Duration::from_secs(8));
+
+    let random = RetryPolicy { backoff: Backoff::Random, ..fixed.clone() };
+    for attempt in 1..20 {
+        assert!(random.delay_after_attempt(attempt) < Duration::from_secs(2));
+    }
+}
+
+#[test]
+fn random_backoff_of_nothing_is_nothing() {
+    assert_eq!(backoff_rand(Duration::from_secs(0)), Duration::from_secs(0));
+}
+
+fn mock_requires_pairs(_: &str, _: &[(String, String)], body: &str) -> Result<u32, (u32, String)> {
+    if split_batch(body).len() == 2 && body.starts_with("[") {
+        Ok(200)
+    } else {
+        Err((400, "Bad Request".to_string()))
+    }
+}
+
+fn make_task_update(to_state: State) -> ExecutionUpdate {
+    ExecutionUpdate::new(ExecutionState::Running,
+                         TaskSnapshot::new(),
+                         Transition::Task(vec![TaskTransition::new("apple",
+                                                                   State::Running,
+                                                                   to_state)]))
+}
+
+#[test]
+fn event_filter_from_names() {
+    assert_eq!(EventFilter::from_names("all"), Ok(EventFilter::All));
+    assert_eq!(EventFilter::from_names("job,all"), Ok(EventFilter::All));
+    assert_eq!(EventFilter::from_names("job, task-failures"),
+               Ok(EventFilter::Only(vec![EventKind::Job, EventKind::TaskFailure])));
+    assert_eq!(EventFilter::from_names("job,tasks"),
+               Err("unknown webhook event 'tasks' (expected all, job, task or task-failures)"
+                   .to_string()));
+}
+
+#[test]
+fn event_filter_matches() {
+    let job = make_mock_run()[0].clone();
+    let task_ok = make_task_update(State::Success);
+    let task_failed = make_task_update(State::Failed("oh no".to_string()));
+
+    let jobs_only = EventFilter::Only(vec![EventKind::Job]);
+    assert!(jobs_only.matches(&job));
+    assert!(!jobs_only.matches(&task_ok));
+
+    let tasks = EventFilter::Only(vec![EventKind::Task]);
+    assert!(!tasks.matches(&job));
+    assert!(tasks.matches(&task_ok));
+    assert!(tasks.matches(&task_failed));
+
+    let failures = EventFilter::Only(vec![EventKind::TaskFailure]);
+    assert!(!failures.matches(&job));
+    assert!(!failures.matches(&task_ok));
+    assert!(failures.matches(&task_failed));
+
+    assert!(EventFilter::All.matches(&task_ok));
+}
+
+#[test]
+fn filtered_events_not_sent() {
+    let mut wh = Webhook::new("job_name", "hello", "https://goodplace.com", None, None);
+    wh.event_filter = EventFilter::Only(vec![EventKind::TaskFailure]);
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_200_ok, zero_backoff);
+
+    for state in make_mock_run().iter() {
+        tx.send(state.clone()).unwrap();
+    }
+
+    let result = jh.join().ok().unwrap();
+    assert_eq!(result.events_received, 4);
+    assert_eq!(result.filtered_count, 4);
+    assert_eq!(result.success_count, 0);
+    assert_eq!(result.undelivered_count, 0);
+}
+
+#[test]
+fn updates_batched_by_size() {
+    let mut wh = Webhook::new("job_name", "hello", "https://goodplace.com", None, None);
+    wh.batching = Batching {
+        size: 2,
+        interval: None,
+    };
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_requires_pairs, zero_backoff);
+
+    for state in make_mock_run().iter() {
+        tx.send(state.clone()).unwrap();
+    }
+
+    let result = jh.join().ok().unwrap();
+    assert_eq!(result.events_received, 4);
+    assert_eq!(result.success_count, 2);
+    assert_eq!(result.failed_count, 0);
+}
+
+#[test]
+fn partial_batch_sent_when_job_finishes() {
+    let mut wh = Webhook::new("job_name", "hello", "https://goodplace.com", None, None);
+    wh.batching = Batching {
+        size: 10,
+        interval: Some(Duration::from_secs(600)),
+    };
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_500_err, zero_backoff);
+
+    for state in make_mock_run().iter() {
+        tx.send(state.clone()).unwrap();
+    }
+
+    let result = jh.join().ok().unwrap();
+    assert_eq!(result.events_received, 4);
+    assert_eq!(result.failed_count, 3);
+    assert_eq!(result.undelivered_count, 4);
+}
+
+#[test]
+fn batch_sent_once_interval_passes() {
+    let mut wh = Webhook::new("job_name", "hello", "https://goodplace.com", None, None);
+    wh.batching = Batching {
+        size: 10,
+        interval: Some(Duration::from_millis(50)),
+    };
+    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+    let jh = wh.connect_webhook(rx, mock_requires_pairs, zero_backoff);
+
+    let sent_states = make_mock_run();
+    tx.send(sent_states[0].clone()).unwrap();
+    tx.send(sent_states[1].clone()).unwrap();
+    thread::sleep(Duration::from_millis(500));
+    tx.send(sent_states[2].clone()).unwrap();
+    tx.send(sent_states[3].clone()).unwrap();
+
+    let result = jh.join().ok().unwrap();
+    assert_eq!(result.success_count, 2);
+}
+
+#[test]
+fn batches_split_back_up() {
+    let batch = as_batch(&["{\"a\":1}".to_string(), "{\"b\":2}".to_string()]);
+    assert_eq!(batch, "[{\"a\":1},{\"b\":2}]");
+    assert_eq!(split_batch(&batch),
+               vec!["{\"a\":1}".to_string(), "{\"b\":2}".to_string()]);
+    assert_eq!(split_batch("{\"a\":1}"), vec!["{\"a\":1}".to_string()]);
+}
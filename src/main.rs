@@ -37,8 +37,16 @@ use factotum::factfile::Factfile;
 use factotum::factfile::Task as FactfileTask;
 use factotum::parser::OverrideResultMappings;
 use factotum::parser::TaskReturnCodeMapping;
+use factotum::parser::formats::Format;
 use factotum::executor::execution_strategy::*;
 use factotum::webhook::Webhook;
+use factotum::webhook::sink::Sink;
+use factotum::webhook::jobcontext::JobContext;
+use factotum::runstate::RunState;
+use factotum::tasklog;
+use factotum::tasklog::AttemptLogs;
+use factotum::cancellation;
+use factotum::report::{RunReport, ReportFormat, JobOutcome};
 use factotum::executor::ExecutionUpdate;
 use factotum::webhook;
 use colored::*;
@@ -47,7 +55,6 @@ use std::process::Command;
 use std::io::Write;
 use std::fs::OpenOptions;
 use std::env;
-use hyper::Url;
 use std::sync::mpsc;
 use std::net;
 use rustc_serialize::json::{self, Json, ToJson};
@@ -72,9 +79,11 @@ const USAGE: &'static str =
 Factotum.
 
 Usage:
-  factotum run <factfile> [--start=<start_task>] [--env=<env>] [--dry-run] [--no-colour] [--webhook=<url>] [--tag=<tag>]... [--constraint=<constraint>]... [--max-stdouterr-size=<bytes>]
-  factotum validate <factfile> [--no-colour]
-  factotum dot <factfile> [--start=<start_task>] [--output=<output_file>] [--overwrite] [--no-colour]
+  factotum run <factfile> [--start=<start_task>] [--env=<env>] [--dry-run] [--no-colour] [--webhook=<url>]... [--webhook-header=<header>]... [--webhook-token-env=<var>] [--webhook-token-file=<file>] [--webhook-secret-env=<var>] [--webhook-secret-file=<file>] [--webhook-spool=<dir>] [--webhook-max-attempts=<n>] [--webhook-backoff=<backoff>] [--webhook-backoff-delay=<seconds>] [--webhook-connect-timeout=<seconds>] [--webhook-read-timeout=<seconds>] [--webhook-events=<events>] [--webhook-batch-size=<n>] [--webhook-batch-interval=<seconds>] [--tag=<tag>]... [--constraint=<constraint>]... [--max-stdouterr-size=<bytes>] [--max-parallel=<n>] [--grace-period=<seconds>] [--format=<format>] [--log-dir=<dir>] [--report=<report_file>] [--report-format=<report_format>]
+  factotum resume <run-state-file> [--no-colour] [--webhook=<url>]... [--webhook-header=<header>]... [--webhook-token-env=<var>] [--webhook-token-file=<file>] [--webhook-secret-env=<var>] [--webhook-secret-file=<file>] [--webhook-spool=<dir>] [--webhook-max-attempts=<n>] [--webhook-backoff=<backoff>] [--webhook-backoff-delay=<seconds>] [--webhook-connect-timeout=<seconds>] [--webhook-read-timeout=<seconds>] [--webhook-events=<events>] [--webhook-batch-size=<n>] [--webhook-batch-interval=<seconds>] [--max-stdouterr-size=<bytes>] [--max-parallel=<n>] [--grace-period=<seconds>] [--log-dir=<dir>] [--report=<report_file>] [--report-format=<report_format>]
+  factotum webhook flush <spool-dir> --webhook=<url> [--webhook-header=<header>]... [--webhook-token-env=<var>] [--webhook-token-file=<file>] [--webhook-secret-env=<var>] [--webhook-secret-file=<file>] [--webhook-connect-timeout=<seconds>] [--webhook-read-timeout=<seconds>] [--no-colour]
+  factotum validate <factfile> [--no-colour] [--format=<format>]
+  factotum dot <factfile> [--start=<start_task>] [--output=<output_file>] [--overwrite] [--no-colour] [--format=<format>]
   factotum (-h | --help) [--no-colour]
   factotum (-v | --version) [--no-colour]
 
@@ -87,10 +96,30 @@ Options:
   --output=<output_file>                File to print output to. Used with `dot`.
   --overwrite                           Overwrite the output file if it exists.
   --no-colour                           Turn off ANSI terminal colours/formatting in output.
-  --webhook=<url>                       Post updates on job execution to the specified URL.
+  --webhook=<url>                       Post updates on job execution to the specified URL (can be repeated). Also accepts snowplow://<collector-host>, file:///path/to/events.jsonl, stdout:// and stderr://.
+  --webhook-header=<header>             Add a header (\"Name: value\") to each webhook request.
+  --webhook-token-env=<var>             Send the bearer token held in this environment variable with each webhook request.
+  --webhook-token-file=<file>           Send the bearer token held in this file with each webhook request.
+  --webhook-secret-env=<var>            Sign each webhook request (HMAC-SHA256) with the secret held in this environment variable.
+  --webhook-secret-file=<file>          Sign each webhook request (HMAC-SHA256) with the secret held in this file.
+  --webhook-spool=<dir>                 Save webhook updates that couldn't be sent to this directory, to be sent later with `webhook flush` (one subdirectory per URL if --webhook is repeated).
+  --webhook-max-attempts=<n>            How many times to try sending each webhook update [default: 3].
+  --webhook-backoff=<backoff>           How to wait between webhook attempts (random, exponential or fixed) [default: random].
+  --webhook-backoff-delay=<seconds>     The wait between webhook attempts (the longest wait for random, the first wait for exponential) [default: 60].
+  --webhook-connect-timeout=<seconds>   Give up on connecting to a webhook URL after this long.
+  --webhook-read-timeout=<seconds>      Give up on a webhook request that stops sending or receiving data for this long.
+  --webhook-events=<events>             Which updates to send to webhooks, as a comma separated list of all, job, task or task-failures [default: all].
+  --webhook-batch-size=<n>              Send webhook updates in batches (a JSON array) of up to this many [default: 1].
+  --webhook-batch-interval=<seconds>    Send a batch of webhook updates once its first update has waited this long.
   --tag=<tag>                           Add job metadata (tags).
   --constraint=<constraint>             Checks for an external constraint that will prevent execution; allowed constraints (host).
   --max-stdouterr-size=<bytes>          The maximum size of the individual stdout/err sent via the webhook functions for job updates.
+  --max-parallel=<n>                    The maximum number of tasks to run at the same time.
+  --grace-period=<seconds>              How long running tasks are given to stop after Factotum passes on a SIGINT or SIGTERM, before they're killed - a second signal kills Factotum straight away [default: 10].
+  --format=<format>                     The Factfile's format (json, yaml or toml), if it can't be told from the file extension.
+  --log-dir=<dir>                       Save the full stdout and stderr of each task attempt to <dir>/<run-reference>/<task>.<attempt>.out and .err as it runs.
+  --report=<report_file>                Write a summary of the run to this file once the job has finished.
+  --report-format=<report_format>       The format of the run summary written by --report (json or junit) [default: json].
 ";
 
 #[derive(Debug, RustcDecodable)]
@@ -98,16 +127,41 @@ struct Args {
     flag_start: Option<String>,
     flag_env: Option<String>,
     flag_output: Option<String>,
-    flag_webhook: Option<String>,
+    flag_webhook: Option<Vec<String>>,
+    flag_webhook_header: Option<Vec<String>>,
+    flag_webhook_token_env: Option<String>,
+    flag_webhook_token_file: Option<String>,
+    flag_webhook_secret_env: Option<String>,
+    flag_webhook_secret_file: Option<String>,
+    flag_webhook_spool: Option<String>,
+    flag_webhook_max_attempts: u32,
+    flag_webhook_backoff: String,
+    flag_webhook_backoff_delay: u64,
+    flag_webhook_connect_timeout: Option<u64>,
+    flag_webhook_read_timeout: Option<u64>,
+    flag_webhook_events: String,
+    flag_webhook_batch_size: usize,
+    flag_webhook_batch_interval: Option<u64>,
     flag_overwrite: bool,
     flag_dry_run: bool,
     flag_no_colour: bool,
     flag_tag: Option<Vec<String>>,
     flag_constraint: Option<Vec<String>>,
     flag_max_stdouterr_size: Option<usize>,
+    flag_max_parallel: Option<usize>,
+    flag_grace_period: u64,
+    flag_format: Option<String>,
+    flag_log_dir: Option<String>,
+    flag_report: Option<String>,
+    flag_report_format: String,
     arg_factfile: String,
+    arg_run_state_file: String,
+    arg_spool_dir: String,
     flag_version: bool,
     cmd_run: bool,
+    cmd_resume: bool,
+    cmd_webhook: bool,
+    cmd_flush: bool,
     cmd_validate: bool,
     cmd_dot: bool,
 }
@@ -165,9 +219,28 @@ fn get_task_result_line_str(task_result: &Task<&FactfileTask>) -> (String, Optio
         task_result.run_result {
         // we know tasks with run details were attempted
 
-        let opener = format!("Task '{}' was started at {}\n",
-                             task_result.name.cyan(),
-                             start_time.unwrap());
+        let mut opener = format!("Task '{}' was started at {}\n",
+                                 task_result.name.cyan(),
+                                 start_time.unwrap());
+
+        for (attempt, previous) in res.previous_attempts.iter().enumerate() {
+            opener.push_str(&format!("Task '{}' attempt {} returned {} after {}, retrying\n",
+                                     task_result.name.cyan(),
+                                     attempt + 1,
+                                     previous.return_code,
+                                     get_duration_as_string(&previous.duration)));
+        }
+
+        let attempts = if res.previous_attempts.is_empty() {
+            String::new()
+        } else {
+            format!(" on attempt {}", res.previous_attempts.len() + 1)
+        };
+
+        let killed_by = match res.terminating_signal {
+            Some(signal) => format!(" (killed by {})", cancellation::signal_name(signal)),
+            None => String::new(),
+        };
 
         let output = match res.stdout {
             Some(ref o) => {
@@ -197,34 +270,76 @@ fn get_task_result_line_str(task_result: &Task<&FactfileTask>) -> (String, Optio
             (_, State::Failed(fail_reason)) => {
                 let mut failure_str = "Task '".red().to_string();
                 failure_str.push_str(&format!("{}", task_result.name.cyan()));
-                failure_str.push_str(&format!("': failed after {}. Reason: {}",
+                failure_str.push_str(&format!("': failed after {}{}. Reason: {}",
                                               get_duration_as_string(&res.duration),
+                                              attempts,
                                               fail_reason)
                     .red()
                     .to_string());
                 failure_str
             }
+            (_, State::Cancelled(cancel_reason)) => {
+                let mut cancel_str = "Task '".red().to_string();
+                cancel_str.push_str(&format!("{}", task_result.name.cyan()));
+                cancel_str.push_str(&format!("': was cancelled after {}{}{}. Reason: {}",
+                                             get_duration_as_string(&res.duration),
+                                             attempts,
+                                             killed_by,
+                                             cancel_reason)
+                    .red()
+                    .to_string());
+                cancel_str
+            }
             (_, _) => {
                 let mut success_str = "Task '".green().to_string();
                 success_str.push_str(&format!("{}", task_result.name.cyan()));
-                success_str.push_str(&format!("': succeeded after {}",
-                                              get_duration_as_string(&res.duration))
+                success_str.push_str(&format!("': succeeded after {}{}{}",
+                                              get_duration_as_string(&res.duration),
+                                              attempts,
+                                              killed_by)
                     .green()
                     .to_string());
                 success_str
             }
         };
 
+        // processes the task left behind aren't part of its output, so they're reported
+        // separately
+        let summary = match res.leftover_processes {
+            Some(ref msg) => {
+                let mut leftovers_str = summary;
+                leftovers_str.push_str("\n");
+                leftovers_str.push_str(&"Task '".yellow().to_string());
+                leftovers_str.push_str(&format!("{}", task_result.name.cyan()));
+                leftovers_str.push_str(&format!("': {}", msg).yellow().to_string());
+                leftovers_str
+            }
+            None => summary,
+        };
+
+        let summary = match res.logs {
+            Some(ref logs) => {
+                format!("{}\nTask '{}': the full output was saved to '{}' and '{}'",
+                        summary,
+                        task_result.name.cyan(),
+                        logs.stdout.display(),
+                        logs.stderr.display())
+            }
+            None => summary,
+        };
+
         (opener, output, errors, summary)
 
     } else {
         // tasks without run details may have been unable to start (some internal error)
         // or skipped because a prior task errored or NOOPed
 
-        let reason_for_not_running = if let State::Failed(_) = task_result.state {
-            "Factotum could not start the task".red().to_string()
-        } else {
-            "skipped".to_string()
+        let reason_for_not_running = match task_result.state {
+            State::Failed(_) => "Factotum could not start the task".red().to_string(),
+            State::Success | State::SuccessNoop => {
+                "already completed in a previous run".green().to_string()
+            }
+            _ => "skipped".to_string(),
         };
 
         let opener = format!("Task '{}': {}!\n",
@@ -275,6 +390,13 @@ fn get_task_results_str(task_results: &Vec<&Task<&FactfileTask>>) -> (String, St
     (stdout, stderr)
 }
 
+fn is_completed(state: &State) -> bool {
+    match *state {
+        State::Success | State::SuccessNoop => true,
+        _ => false,
+    }
+}
+
 fn validate_start_task(job: &Factfile, start_task: &str) -> Result<(), &'static str> {
     // A
     // / \
@@ -306,8 +428,15 @@ fn validate_start_task(job: &Factfile, start_task: &str) -> Result<(), &'static
     }
 }
 
-fn dot(factfile: &str, start_from: Option<String>) -> Result<String, String> {
-    let ff = try!(factotum::parser::parse(factfile, None, OverrideResultMappings::None));
+fn dot(factfile: &str,
+       format: Option<Format>,
+       start_from: Option<String>)
+       -> Result<String, String> {
+    let ff = try!(factotum::parser::parse_with_format(factfile,
+                                                      format,
+                                                      None,
+                                                      OverrideResultMappings::None)
+        .map_err(|e| e.to_string()));
     if let Some(ref start) = start_from {
         match ff.can_job_run_from_task(&start) {
             Ok(is_good) => {
@@ -322,15 +451,55 @@ fn dot(factfile: &str, start_from: Option<String>) -> Result<String, String> {
     Ok(ff.as_dotfile(start_from))
 }
 
-fn validate(factfile: &str, env: Option<Json>) -> Result<String, String> {
-    match factotum::parser::parse(factfile, env, OverrideResultMappings::None) {
+fn validate(factfile: &str, format: Option<Format>, env: Option<Json>) -> Result<String, String> {
+    match factotum::parser::parse_with_format(factfile,
+                                              format,
+                                              env,
+                                              OverrideResultMappings::None) {
         Ok(_) => Ok(format!("'{}' is a valid Factfile!", factfile).green().to_string()),
-        Err(msg) => Err(msg.red().to_string()),
+        Err(err) => Err(err.to_string().red().to_string()),
     }
 }
 
-fn parse_file_and_simulate(factfile: &str, env: Option<Json>, start_from: Option<String>) -> i32 {
+// where the updates for a run are sent, and how
+#[derive(Debug, Clone, Default)]
+struct WebhookOptions {
+    urls: Vec<String>,
+    headers: Vec<(String, String)>,
+    secret: Option<String>,
+    spool: Option<String>,
+    retry: webhook::RetryPolicy,
+    timeouts: webhook::HttpTimeouts,
+    filter: webhook::EventFilter,
+    batching: webhook::Batching,
+}
+
+// the settings shared by 'run' and 'resume' that aren't about the job itself
+#[derive(Debug, Clone)]
+struct RunOptions {
+    max_parallel: Option<usize>,
+    max_stdouterr_size: Option<usize>,
+    log_dir: Option<String>,
+    report_file: Option<String>,
+    report_format: ReportFormat,
+    webhook: WebhookOptions,
+}
+
+fn parse_file_and_simulate(factfile: &str,
+                           format: Option<Format>,
+                           env: Option<Json>,
+                           start_from: Option<String>,
+                           options: &RunOptions)
+                           -> i32 {
+    // a dry run only reports on the job, nothing is sent or saved
+    let simulate_options = RunOptions {
+        max_stdouterr_size: None,
+        log_dir: None,
+        webhook: WebhookOptions::default(),
+        ..options.clone()
+    };
     parse_file_and_execute_with_strategy(factfile,
+                                         format,
                                          env,
                                          start_from,
                                          factotum::executor::execution_strategy::execute_simulation,
@@ -339,40 +508,75 @@ fn parse_file_and_simulate(factfile: &str, env: Option<Json>, start_from: Option
                                              terminate_early: vec![],
                                          }),
                                          None,
-                                         None,
+                                         &simulate_options,
                                          None)
 }
 
 fn parse_file_and_execute(factfile: &str,
+                          format: Option<Format>,
                           env: Option<Json>,
                           start_from: Option<String>,
-                          webhook_url: Option<String>,
                           job_tags: Option<HashMap<String, String>>,
-                          max_stdouterr_size: Option<usize>)
+                          options: &RunOptions)
                           -> i32 {
     parse_file_and_execute_with_strategy(factfile,
+                                         format,
                                          env,
                                          start_from,
                                          factotum::executor::execution_strategy::execute_os,
                                          OverrideResultMappings::None,
-                                         webhook_url,
                                          job_tags,
-                                         max_stdouterr_size)
+                                         options,
+                                         None)
+}
+
+fn load_state_and_resume(run_state_file: &str, options: &RunOptions) -> i32 {
+    match RunState::load(run_state_file) {
+        Ok(run_state) => {
+            println!("Resuming run {} of '{}'",
+                     run_state.run_reference,
+                     run_state.job_name.cyan());
+            let job_tags = Some(run_state.tags.clone());
+            let start_from = run_state.start_from.clone();
+            parse_file_and_execute_with_strategy(run_state_file,
+                                                 None,
+                                                 None,
+                                                 start_from,
+                                                 factotum::executor::execution_strategy::execute_os,
+                                                 OverrideResultMappings::None,
+                                                 job_tags,
+                                                 options,
+                                                 Some(run_state))
+        }
+        Err(msg) => {
+            println!("{}", msg.red());
+            PROC_OTHER_ERROR
+        }
+    }
 }
 
 fn parse_file_and_execute_with_strategy<F>(factfile: &str,
+                                           format: Option<Format>,
                                            env: Option<Json>,
                                            start_from: Option<String>,
                                            strategy: F,
                                            override_result_map: OverrideResultMappings,
-                                           webhook_url: Option<String>,
                                            job_tags: Option<HashMap<String, String>>,
-                                           max_stdouterr_size: Option<usize>)
+                                           options: &RunOptions,
+                                           resume_state: Option<RunState>)
                                            -> i32
-    where F: Fn(&str, &mut Command) -> RunResult + Send + Sync + 'static + Copy
+    where F: Fn(&str, &mut Command, Option<Duration>, Option<&AttemptLogs>) -> RunResult
+             + Send + Sync + 'static + Copy
 {
 
-    match factotum::parser::parse(factfile, env, override_result_map) {
+    let parse_result = if let Some(ref run_state) = resume_state {
+        // the saved factfile has already had any variables applied to it
+        factotum::parser::parse_str(&run_state.factfile, factfile, None, override_result_map)
+    } else {
+        factotum::parser::parse_with_format(factfile, format, env, override_result_map)
+    };
+
+    match parse_result {
         Ok(job) => {
 
             if let Some(ref start_task) = start_from {
@@ -387,21 +591,85 @@ fn parse_file_and_execute_with_strategy<F>(factfile: &str,
                 }
             }
 
-            let (maybe_updates_channel, maybe_join_handle) = if webhook_url.is_some() {
-                let url = webhook_url.unwrap();
-                let mut wh = Webhook::new(job.name.clone(), job.raw.clone(), url, job_tags, max_stdouterr_size);
+            let mut job_context = JobContext::new(job.name.clone(), &job.raw, job_tags);
+            let completed_tasks = if let Some(ref run_state) = resume_state {
+                // a resumed run carries on reporting under its original run reference
+                job_context.run_reference = run_state.run_reference.clone();
+                Some(run_state.get_completed_tasks())
+            } else {
+                None
+            };
+
+            let mut webhook_join_handles = vec![];
+            let webhook_options = &options.webhook;
+            let maybe_updates_channel = if !webhook_options.urls.is_empty() {
+                let mut subscribers = vec![];
+                for url in webhook_options.urls.iter() {
+                    let spool_dir = webhook_options.spool.as_ref().map(|dir| {
+                        if webhook_options.urls.len() > 1 {
+                            webhook::get_endpoint_spool_dir(dir, url)
+                        } else {
+                            dir.clone()
+                        }
+                    });
+                    let mut wh = Webhook::from_job_context(job_context.clone(),
+                                                           job.raw.clone(),
+                                                           url.clone(),
+                                                           webhook_options.headers.clone(),
+                                                           webhook_options.secret.clone(),
+                                                           spool_dir.clone(),
+                                                           webhook_options.retry.max_attempts,
+                                                           options.max_stdouterr_size);
+                    wh.event_filter = webhook_options.filter.clone();
+                    wh.batching = webhook_options.batching.clone();
+                    let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
+                    let retry = webhook_options.retry.clone();
+                    let timeouts = webhook_options.timeouts;
+                    let join_handle =
+                        wh.connect_webhook(rx,
+                                           move |url, headers, data| {
+                                               webhook::sink::emit(url,
+                                                                   headers,
+                                                                   data,
+                                                                   &timeouts)
+                                           },
+                                           move |attempt| retry.delay_after_attempt(attempt));
+                    subscribers.push(tx);
+                    webhook_join_handles.push((url.clone(), spool_dir, join_handle));
+                }
+
                 let (tx, rx) = mpsc::channel::<ExecutionUpdate>();
-                let join_handle =
-                    wh.connect_webhook(rx, Webhook::http_post, webhook::backoff_rand_1_minute);
-                (Some(tx), Some(join_handle))
+                webhook::fan_out(rx, subscribers);
+                Some(tx)
             } else {
-                (None, None)
+                None
+            };
+
+            // the log files are written while the tasks run, so the directory has to exist first
+            let run_log_dir = match options.log_dir {
+                Some(ref dir) => {
+                    match tasklog::create_run_log_dir(dir, &job_context.run_reference) {
+                        Ok(run_dir) => Some(run_dir),
+                        Err(msg) => {
+                            println!("{}",
+                                     format!("Warning: the output of the tasks couldn't be \
+                                              saved: {}",
+                                             msg)
+                                         .yellow());
+                            None
+                        }
+                    }
+                }
+                None => None,
             };
 
             let job_res = factotum::executor::execute_factfile(&job,
-                                                               start_from,
+                                                               start_from.clone(),
+                                                               options.max_parallel,
+                                                               completed_tasks,
                                                                strategy,
-                                                               maybe_updates_channel);
+                                                               maybe_updates_channel,
+                                                               run_log_dir.clone());
 
             let mut has_errors = false;
             let mut has_early_finish = false;
@@ -419,7 +687,14 @@ fn parse_file_and_execute_with_strategy<F>(factfile: &str,
                 }
             }
 
-            let normal_completion = !has_errors && !has_early_finish;
+            let cancelled_by = cancellation::cancel_requested();
+            let has_cancelled = cancelled_by.is_some() &&
+                                tasks.iter().any(|t| match t.state {
+                State::Cancelled(_) | State::Skipped(_) => true,
+                _ => false,
+            });
+
+            let normal_completion = !has_errors && !has_early_finish && !has_cancelled;
 
             let result = if normal_completion {
                 let (stdout_summary, stderr_summary) = get_task_results_str(&tasks);
@@ -428,14 +703,14 @@ fn parse_file_and_execute_with_strategy<F>(factfile: &str,
                     print_err!("{}", stderr_summary.trim_right());
                 }
                 PROC_SUCCESS
-            } else if has_early_finish && !has_errors {
+            } else if has_early_finish && !has_errors && !has_cancelled {
                 let (stdout_summary, stderr_summary) = get_task_results_str(&tasks);
                 print!("{}", stdout_summary);
                 if !stderr_summary.trim_right().is_empty() {
                     print_err!("{}", stderr_summary.trim_right());
                 }
                 let incomplete_tasks = tasks.iter()
-                    .filter(|r| !r.run_result.is_some())
+                    .filter(|r| !r.run_result.is_some() && !is_completed(&r.state))
                     .map(|r| format!("'{}'", r.name.cyan()))
                     .collect::<Vec<String>>()
                     .join(", ");
@@ -461,7 +736,7 @@ fn parse_file_and_execute_with_strategy<F>(factfile: &str,
                 }
 
                 let incomplete_tasks = tasks.iter()
-                    .filter(|r| !r.run_result.is_some())
+                    .filter(|r| !r.run_result.is_some() && !is_completed(&r.state))
                     .map(|r| format!("'{}'", r.name.cyan()))
                     .collect::<Vec<String>>()
                     .join(", ");
@@ -475,21 +750,97 @@ fn parse_file_and_execute_with_strategy<F>(factfile: &str,
                     .collect::<Vec<String>>()
                     .join(", ");
 
-                println!("Factotum job executed abnormally as a task ({}) failed - the following \
-                          tasks were not run: {}!",
-                         failed_tasks,
-                         incomplete_tasks);
+                if has_cancelled {
+                    println!("Factotum job was cancelled by {} - the following tasks were not \
+                              run: {}!",
+                             cancellation::signal_name(cancelled_by.unwrap()),
+                             incomplete_tasks);
+                } else {
+                    println!("Factotum job executed abnormally as a task ({}) failed - the \
+                              following tasks were not run: {}!",
+                             failed_tasks,
+                             incomplete_tasks);
+                }
+
+                let task_snapshot = factotum::executor::get_task_snapshot(&job_res);
+                let run_state = RunState::new(&job_context,
+                                              &job.raw,
+                                              start_from,
+                                              &task_snapshot);
+                let run_state_file = run_state.get_default_filename();
+                match run_state.save(&run_state_file) {
+                    Ok(_) => {
+                        println!("The state of this run has been saved - use 'factotum resume \
+                                  {}' to run the failed and skipped tasks again.",
+                                 run_state_file)
+                    }
+                    Err(msg) => {
+                        println!("{}",
+                                 format!("Warning: the state of this run couldn't be saved: {}",
+                                         msg)
+                                     .yellow())
+                    }
+                }
+
                 PROC_EXEC_ERROR
             };
 
-            if maybe_join_handle.is_some() {
+            if let Some(ref run_dir) = run_log_dir {
+                println!("The full output of each task has been saved to '{}'",
+                         run_dir.display())
+            }
+
+            if let Some(ref report_file) = options.report_file {
+                let outcome = if has_cancelled {
+                    JobOutcome::CANCELLED
+                } else if normal_completion {
+                    JobOutcome::SUCCEEDED
+                } else if has_early_finish && !has_errors {
+                    JobOutcome::FINISHED_EARLY
+                } else {
+                    JobOutcome::FAILED
+                };
+                let report = RunReport::new(&job_context,
+                                            &factotum::executor::get_task_snapshot(&job_res),
+                                            outcome,
+                                            result,
+                                            options.max_stdouterr_size.unwrap_or(10_000));
+                match write_to_file(report_file, &report.render(options.report_format), true) {
+                    Ok(_) => println!("The run report has been written to '{}'", report_file),
+                    Err(msg) => {
+                        println!("{}",
+                                 format!("Warning: the run report couldn't be written: {}", msg)
+                                     .yellow())
+                    }
+                }
+            }
+
+            if !webhook_join_handles.is_empty() {
+                // the webhooks stop retrying once the job is cancelled (including by a signal
+                // sent while waiting here), so a cancelled run isn't held up by a failing endpoint
                 print!("Waiting for webhook to finish sending events...");
-                let j = maybe_join_handle.unwrap();
-                let webhook_res = j.join().ok().unwrap();
+                let webhook_results = webhook_join_handles.into_iter()
+                    .map(|(url, spool_dir, j)| (url, spool_dir, j.join().ok().unwrap()))
+                    .collect::<Vec<_>>();
                 println!("{}", " done!".green());
 
-                if webhook_res.events_received > webhook_res.success_count {
-                    println!("{}", "Warning: some events failed to send".red());
+                for (url, spool_dir, webhook_res) in webhook_results {
+                    if webhook_res.spooled_count > 0 {
+                        let spool_dir = spool_dir.unwrap();
+                        println!("{}",
+                                 format!("Warning: {} events failed to send to {} and were \
+                                          saved to '{}'",
+                                         webhook_res.spooled_count,
+                                         url,
+                                         spool_dir)
+                                     .red());
+                        println!("They can be sent with 'factotum webhook flush {} --webhook={}'",
+                                 spool_dir,
+                                 url);
+                    } else if webhook_res.undelivered_count > 0 {
+                        println!("{}",
+                                 format!("Warning: some events failed to send to {}", url).red());
+                    }
                 }
             }
 
@@ -502,6 +853,36 @@ fn parse_file_and_execute_with_strategy<F>(factfile: &str,
     }
 }
 
+fn flush_webhook_spool(spool_dir: &str,
+                       url: &str,
+                       headers: &[(String, String)],
+                       secret: &Option<String>,
+                       timeouts: webhook::HttpTimeouts)
+                       -> i32 {
+    let emitter = |url: &str, headers: &[(String, String)], data: &str| {
+        webhook::sink::emit(url, headers, data, &timeouts)
+    };
+    match webhook::spool::flush(spool_dir, url, headers, secret, emitter) {
+        Ok(result) => {
+            println!("Sent {} spooled events to {}", result.sent, url);
+            if let Some(msg) = result.failure {
+                println!("{}",
+                         format!("Error: {}; {} events are still waiting to be sent",
+                                 msg,
+                                 result.remaining)
+                             .red());
+                PROC_OTHER_ERROR
+            } else {
+                PROC_SUCCESS
+            }
+        }
+        Err(msg) => {
+            println!("{}", format!("Error: {}", msg).red());
+            PROC_OTHER_ERROR
+        }
+    }
+}
+
 fn write_to_file(filename: &str, contents: &str, overwrite: bool) -> Result<(), String> {
     let mut f = if overwrite {
         match OpenOptions::new()
@@ -529,14 +910,247 @@ fn write_to_file(filename: &str, contents: &str, overwrite: bool) -> Result<(),
 }
 
 fn is_valid_url(url: &str) -> Result<(), String> {
-    if url.starts_with("http://") || url.starts_with("https://") {
-        match Url::parse(url) {
-            Ok(_) => Ok(()),
-            Err(msg) => Err(format!("{}", msg)),
+    Sink::from_url(url).map(|_| ())
+}
+
+// reads a webhook token/secret from either an environment variable or a file, but not both
+fn read_webhook_credential(name: &str,
+                           from_env: Option<&String>,
+                           from_file: Option<&String>)
+                           -> Result<Option<String>, String> {
+    use std::io::Read;
+
+    let credential = match (from_env, from_file) {
+        (Some(_), Some(_)) => {
+            return Err(format!("--webhook-{}-env and --webhook-{}-file can't be used together",
+                               name,
+                               name))
         }
-    } else {
-        Err("URL must begin with 'http://' or 'https://' to be used with Factotum webhooks".into())
+        (Some(var), None) => {
+            match env::var(var) {
+                Ok(t) => t,
+                Err(_) => {
+                    return Err(format!("the environment variable '{}' holding the webhook {} \
+                                        isn't set",
+                                       var,
+                                       name))
+                }
+            }
+        }
+        (None, Some(file)) => {
+            let mut contents = String::new();
+            match fs::File::open(file).and_then(|mut f| f.read_to_string(&mut contents)) {
+                Ok(_) => contents,
+                Err(e) => {
+                    return Err(format!("couldn't read the webhook {} from '{}' ({})",
+                                       name,
+                                       file,
+                                       e))
+                }
+            }
+        }
+        (None, None) => return Ok(None),
+    };
+
+    let credential = credential.trim();
+    if credential.is_empty() {
+        return Err(format!("the webhook {} is empty", name));
+    }
+    Ok(Some(credential.to_string()))
+}
+
+fn get_webhook_headers(headers: &Vec<String>,
+                       token_env: Option<&String>,
+                       token_file: Option<&String>)
+                       -> Result<Vec<(String, String)>, String> {
+    let mut parsed = vec![];
+    for header in headers.iter() {
+        parsed.push(try!(webhook::parse_header(header)));
+    }
+
+    if let Some(token) = try!(read_webhook_credential("token", token_env, token_file)) {
+        parsed.push(("Authorization".to_string(), format!("Bearer {}", token)));
+    }
+
+    Ok(parsed)
+}
+
+#[test]
+fn test_get_webhook_headers() {
+    let headers = vec!["X-Api-Key: abc".to_string(), "X-Trace: 1".to_string()];
+    assert_eq!(get_webhook_headers(&headers, None, None),
+               Ok(vec![("X-Api-Key".to_string(), "abc".to_string()),
+                       ("X-Trace".to_string(), "1".to_string())]));
+
+    let bad = vec!["X-Api-Key".to_string()];
+    assert!(get_webhook_headers(&bad, None, None).is_err());
+
+    env::set_var("FACTOTUM_TEST_WEBHOOK_TOKEN", " secret\n");
+    let var = "FACTOTUM_TEST_WEBHOOK_TOKEN".to_string();
+    assert_eq!(get_webhook_headers(&vec![], Some(&var), None),
+               Ok(vec![("Authorization".to_string(), "Bearer secret".to_string())]));
+
+    let missing = "FACTOTUM_TEST_WEBHOOK_TOKEN_MISSING".to_string();
+    assert_eq!(get_webhook_headers(&vec![], Some(&missing), None),
+               Err("the environment variable 'FACTOTUM_TEST_WEBHOOK_TOKEN_MISSING' holding the \
+                    webhook token isn't set"
+                   .to_string()));
+
+    let file = "some-file".to_string();
+    assert_eq!(get_webhook_headers(&vec![], Some(&var), Some(&file)),
+               Err("--webhook-token-env and --webhook-token-file can't be used together"
+                   .to_string()));
+}
+
+#[test]
+fn test_get_webhook_headers_token_file() {
+    let mut path = env::temp_dir();
+    path.push("factotum-webhook-token-test.txt");
+    let path_str = path.to_str().unwrap().to_string();
+    fs::File::create(&path).unwrap().write_all(b"from-file\n").unwrap();
+
+    let result = get_webhook_headers(&vec![], None, Some(&path_str));
+    fs::remove_file(&path).ok();
+
+    assert_eq!(result,
+               Ok(vec![("Authorization".to_string(), "Bearer from-file".to_string())]));
+}
+
+#[test]
+fn test_read_webhook_secret() {
+    env::set_var("FACTOTUM_TEST_WEBHOOK_SECRET", "shh");
+    let var = "FACTOTUM_TEST_WEBHOOK_SECRET".to_string();
+    assert_eq!(read_webhook_credential("secret", Some(&var), None),
+               Ok(Some("shh".to_string())));
+    assert_eq!(read_webhook_credential("secret", None, None), Ok(None));
+
+    env::set_var("FACTOTUM_TEST_WEBHOOK_SECRET_BLANK", "  ");
+    let blank = "FACTOTUM_TEST_WEBHOOK_SECRET_BLANK".to_string();
+    assert_eq!(read_webhook_credential("secret", Some(&blank), None),
+               Err("the webhook secret is empty".to_string()));
+}
+
+fn get_webhook_options(args: &Args) -> Result<WebhookOptions, String> {
+    let urls = args.flag_webhook.clone().unwrap_or_default();
+
+    for wh in urls.iter() {
+        if let Err(msg) = is_valid_url(&wh) {
+            return Err(format!("the specifed webhook URL \"{}\" is invalid. Reason: {}",
+                               wh,
+                               msg));
+        }
+    }
+
+    let header_args = args.flag_webhook_header.clone().unwrap_or_default();
+    let headers = try!(get_webhook_headers(&header_args,
+                                           args.flag_webhook_token_env.as_ref(),
+                                           args.flag_webhook_token_file.as_ref()));
+
+    let secret = try!(read_webhook_credential("secret",
+                                              args.flag_webhook_secret_env.as_ref(),
+                                              args.flag_webhook_secret_file.as_ref()));
+
+    let backoff = try!(webhook::Backoff::from_name(&args.flag_webhook_backoff));
+
+    if args.flag_webhook_max_attempts == 0 {
+        return Err("--webhook-max-attempts must allow at least one attempt".to_string());
+    }
+
+    if args.flag_webhook_connect_timeout == Some(0) || args.flag_webhook_read_timeout == Some(0) {
+        return Err("webhook timeouts must be at least one second".to_string());
+    }
+
+    let filter = try!(webhook::EventFilter::from_names(&args.flag_webhook_events));
+
+    if args.flag_webhook_batch_size == 0 || args.flag_webhook_batch_interval == Some(0) {
+        return Err("webhook batches must hold at least one update and wait at least one second"
+            .to_string());
+    }
+
+    if (!headers.is_empty() || secret.is_some() || args.flag_webhook_spool.is_some()) &&
+       urls.is_empty() {
+        return Err("webhook headers, tokens, secrets and spools can only be used with the \
+                    --webhook option"
+            .to_string());
+    }
+
+    Ok(WebhookOptions {
+        urls: urls,
+        headers: headers,
+        secret: secret,
+        spool: args.flag_webhook_spool.clone(),
+        retry: webhook::RetryPolicy {
+            max_attempts: args.flag_webhook_max_attempts,
+            backoff: backoff,
+            delay: Duration::from_secs(args.flag_webhook_backoff_delay),
+        },
+        timeouts: webhook::HttpTimeouts {
+            connect: args.flag_webhook_connect_timeout.map(Duration::from_secs),
+            read: args.flag_webhook_read_timeout.map(Duration::from_secs),
+        },
+        filter: filter,
+        batching: webhook::Batching {
+            size: args.flag_webhook_batch_size,
+            interval: args.flag_webhook_batch_interval.map(Duration::from_secs),
+        },
+    })
+}
+
+fn get_run_options(args: &Args, webhook: WebhookOptions) -> Result<RunOptions, String> {
+    let report_format = try!(ReportFormat::from_name(&args.flag_report_format));
+
+    if args.flag_max_parallel == Some(0) {
+        return Err("--max-parallel must allow at least one task to run".to_string());
     }
+
+    Ok(RunOptions {
+        max_parallel: args.flag_max_parallel,
+        max_stdouterr_size: args.flag_max_stdouterr_size,
+        log_dir: args.flag_log_dir.clone(),
+        report_file: args.flag_report.clone(),
+        report_format: report_format,
+        webhook: webhook,
+    })
+}
+
+#[cfg(test)]
+fn decode_args(argv: &[&str]) -> Args {
+    Docopt::new(USAGE).and_then(|d| d.argv(argv.iter().cloned()).decode()).unwrap()
+}
+
+#[test]
+fn test_get_run_options() {
+    let args = decode_args(&["factotum",
+                             "run",
+                             "job.factfile",
+                             "--max-parallel=2",
+                             "--log-dir=logs",
+                             "--webhook=http://example.com",
+                             "--webhook-max-attempts=5"]);
+    let options = get_webhook_options(&args)
+        .and_then(|webhook| get_run_options(&args, webhook))
+        .unwrap();
+
+    assert_eq!(options.max_parallel, Some(2));
+    assert_eq!(options.log_dir, Some("logs".to_string()));
+    assert_eq!(options.report_format, ReportFormat::Json);
+    assert_eq!(options.webhook.urls, vec!["http://example.com".to_string()]);
+    assert_eq!(options.webhook.retry.max_attempts, 5);
+    assert_eq!(options.webhook.filter, webhook::EventFilter::All);
+}
+
+#[test]
+fn test_get_run_options_invalid() {
+    let no_parallel = decode_args(&["factotum", "run", "job.factfile", "--max-parallel=0"]);
+    assert!(get_run_options(&no_parallel, WebhookOptions::default())
+        .unwrap_err()
+        .contains("--max-parallel"));
+
+    let header_without_webhook =
+        decode_args(&["factotum", "run", "job.factfile", "--webhook-header=a:b"]);
+    assert!(get_webhook_options(&header_without_webhook)
+        .unwrap_err()
+        .contains("can only be used with the --webhook option"));
 }
 
 fn get_constraint_map(constraints: &Vec<String>) -> HashMap<String, String> {
@@ -762,15 +1376,15 @@ fn factotum() -> i32 {
         }
     };
 
-    let tag_map = if let Some(tags) = args.flag_tag {
-        Some(get_tag_map(&tags))
+    let tag_map = if let Some(ref tags) = args.flag_tag {
+        Some(get_tag_map(tags))
     } else {
         None
     };
 
     // Environment should always be present as tags can populate the env
-    let env_str: String = if let Some(c) = args.flag_env {
-        c
+    let env_str: String = if let Some(ref c) = args.flag_env {
+        c.clone()
     } else {
         "{}".to_string()
     };
@@ -811,21 +1425,38 @@ fn factotum() -> i32 {
         return PROC_SUCCESS;
     }
 
-    if args.flag_dry_run && args.flag_webhook.is_some() {
+    let format = if let Some(ref format_name) = args.flag_format {
+        match Format::from_name(format_name) {
+            Ok(f) => Some(f),
+            Err(msg) => {
+                println!("{}", format!("Error: {}", msg).red());
+                return PROC_OTHER_ERROR;
+            }
+        }
+    } else {
+        None
+    };
+
+    let run_options = match get_webhook_options(&args)
+        .and_then(|webhook| get_run_options(&args, webhook)) {
+        Ok(o) => o,
+        Err(msg) => {
+            println!("{}", format!("Error: {}", msg).red());
+            return PROC_OTHER_ERROR;
+        }
+    };
+
+    if args.flag_dry_run && !run_options.webhook.urls.is_empty() {
         println!("{}",
                  "Error: --webhook cannot be used with the --dry-run option".red());
         return PROC_OTHER_ERROR;
     }
 
-    if let Some(ref wh) = args.flag_webhook {
-        if let Err(msg) = is_valid_url(&wh) {
-            println!("{}",
-                     format!("Error: the specifed webhook URL \"{}\" is invalid. Reason: {}",
-                             wh,
-                             msg)
-                         .red());
-            return PROC_OTHER_ERROR;
-        }
+    if (args.cmd_run && !args.flag_dry_run) || args.cmd_resume {
+        // SIGINT and SIGTERM cancel the job rather than stopping factotum outright, so the
+        // running tasks can be stopped and the webhooks hear how the job ended
+        cancellation::set_grace_period(Duration::from_secs(args.flag_grace_period));
+        cancellation::install_signal_handlers();
     }
 
     if args.cmd_run {
@@ -847,16 +1478,29 @@ fn factotum() -> i32 {
 
         if !args.flag_dry_run {
             parse_file_and_execute(&args.arg_factfile,
+                                   format,
                                    env_json,
                                    args.flag_start,
-                                   args.flag_webhook,
                                    tag_map,
-                                   args.flag_max_stdouterr_size)
+                                   &run_options)
         } else {
-            parse_file_and_simulate(&args.arg_factfile, env_json, args.flag_start)
+            parse_file_and_simulate(&args.arg_factfile,
+                                    format,
+                                    env_json,
+                                    args.flag_start,
+                                    &run_options)
         }
+    } else if args.cmd_resume {
+        load_state_and_resume(&args.arg_run_state_file, &run_options)
+    } else if args.cmd_webhook && args.cmd_flush {
+        let webhook_options = &run_options.webhook;
+        flush_webhook_spool(&args.arg_spool_dir,
+                            &webhook_options.urls[0],
+                            &webhook_options.headers,
+                            &webhook_options.secret,
+                            webhook_options.timeouts)
     } else if args.cmd_validate {
-        match validate(&args.arg_factfile, env_json) {
+        match validate(&args.arg_factfile, format, env_json) {
             Ok(msg) => {
                 println!("{}", msg);
                 PROC_SUCCESS
@@ -867,7 +1511,7 @@ fn factotum() -> i32 {
             }
         }
     } else if args.cmd_dot {
-        match dot(&args.arg_factfile, args.flag_start) {
+        match dot(&args.arg_factfile, format, args.flag_start) {
             Ok(dot) => {
                 if let Some(output_file) = args.flag_output {
                     match write_to_file(&output_file, &dot, args.flag_overwrite) {
@@ -916,10 +1560,21 @@ fn test_is_valid_url() {
         Ok(_) => panic!("no http/s?"),
         Err(msg) => {
             assert_eq!(msg,
-                       "URL must begin with 'http://' or 'https://' to be used with Factotum \
-                        webhooks")
+                       "URL must begin with 'http://', 'https://', 'snowplow://', \
+                        'snowplow+http://', 'file://', 'stdout://' or 'stderr://' to be used \
+                        with Factotum webhooks")
         } // this is good
     }
+
+    match is_valid_url("file:///var/log/factotum/events.jsonl") {
+        Ok(_) => (),
+        Err(_) => panic!("file:///var/log/factotum/events.jsonl is a valid url"),
+    }
+
+    match is_valid_url("stdout://") {
+        Ok(_) => (),
+        Err(_) => panic!("stdout:// is a valid url"),
+    }
 }
 
 #[test]
@@ -960,7 +1615,7 @@ fn test_write_to_file() {
 #[test]
 fn validate_ok_factfile_good() {
     let test_file_path = "./tests/resources/example_ok.factfile";
-    let is_valid = validate(test_file_path, None);
+    let is_valid = validate(test_file_path, None, None);
     let expected: String = format!("'{}' is a valid Factfile!", test_file_path).green().to_string();
     assert_eq!(is_valid, Ok(expected));
 }
@@ -968,7 +1623,7 @@ fn validate_ok_factfile_good() {
 #[test]
 fn validate_ok_factfile_bad() {
     let test_file_path = "./tests/resources/invalid_json.factfile";
-    let is_valid = validate(test_file_path, None);
+    let is_valid = validate(test_file_path, None, None);
     match is_valid {
         Ok(_) => panic!("Validation returning valid for invalid file"),
         Err(msg) => {
@@ -1032,7 +1687,14 @@ fn test_get_task_result_line_str() {
             on_result: OnResult {
                 terminate_job: vec![],
                 continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
             },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
         },
         run_result: Some(RunResult {
             duration: Duration::from_secs(20),
@@ -1040,6 +1702,11 @@ fn test_get_task_result_line_str() {
             stdout: Some(String::from("hello world")),
             stderr: None,
             return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: None,
+            logs: None,
+            previous_attempts: vec![],
         }),
     };
 
@@ -1071,7 +1738,14 @@ fn test_get_task_result_line_str() {
             on_result: OnResult {
                 terminate_job: vec![],
                 continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
             },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
         },
         run_result: Some(RunResult {
             duration: Duration::from_secs(20),
@@ -1079,6 +1753,11 @@ fn test_get_task_result_line_str() {
             stdout: Some(String::from("hello world")),
             stderr: Some(String::from("There's errors")),
             return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: None,
+            logs: None,
+            previous_attempts: vec![],
         }),
     };
 
@@ -1111,7 +1790,14 @@ fn test_get_task_result_line_str() {
             on_result: OnResult {
                 terminate_job: vec![],
                 continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
             },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
         },
         state: State::Skipped("for some reason".to_string()),
         run_result: None,
@@ -1135,7 +1821,14 @@ fn test_get_task_result_line_str() {
             on_result: OnResult {
                 terminate_job: vec![],
                 continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
             },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
         },
         run_result: None,
     };
@@ -1146,6 +1839,37 @@ fn test_get_task_result_line_str() {
                get_task_result_line_str(&task_init_fail).0);
     assert_eq!(None, get_task_result_line_str(&task_init_fail).1);
 
+    // task that succeeded before the run was resumed
+    let task_previously_done = Task::<&FactfileTask> {
+        name: String::from("done"),
+        state: State::Success,
+        run_started: None,
+        task_spec: &FactfileTask {
+            name: "hello world".to_string(),
+            depends_on: vec![],
+            executor: "".to_string(),
+            command: "".to_string(),
+            arguments: vec![],
+            on_result: OnResult {
+                terminate_job: vec![],
+                continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
+            },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
+        },
+        run_result: None,
+    };
+
+    assert_eq!(format!("Task '{}': {}!\n",
+                       "done".cyan(),
+                       "already completed in a previous run".green()),
+               get_task_result_line_str(&task_previously_done).0);
+
     let task_failure = Task::<&FactfileTask> {
         name: String::from("fails"),
         // children: vec![],
@@ -1160,7 +1884,14 @@ fn test_get_task_result_line_str() {
             on_result: OnResult {
                 terminate_job: vec![],
                 continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
             },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
         },
         run_result: Some(RunResult {
             duration: Duration::from_secs(20),
@@ -1168,6 +1899,11 @@ fn test_get_task_result_line_str() {
             stdout: Some(String::from("hello world")),
             stderr: Some(String::from("There's errors")),
             return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: None,
+            logs: None,
+            previous_attempts: vec![],
         }),
     };
 
@@ -1189,6 +1925,254 @@ fn test_get_task_result_line_str() {
 
 }
 
+#[test]
+fn test_get_task_result_line_str_retried() {
+    use chrono::UTC;
+    use factotum::executor::execution_strategy::RunResult;
+    use factotum::factfile::{Task as FactfileTask, OnResult};
+
+    let dt = UTC::now();
+    let failed_attempt = RunResult {
+        duration: Duration::from_secs(2),
+        task_execution_error: None,
+        stdout: None,
+        stderr: None,
+        return_code: 1,
+        timed_out: false,
+        terminating_signal: None,
+        leftover_processes: None,
+        logs: None,
+        previous_attempts: vec![],
+    };
+
+    let retried_task = Task::<&FactfileTask> {
+        name: String::from("copy"),
+        state: State::Success,
+        run_started: Some(dt),
+        task_spec: &FactfileTask {
+            name: "copy".to_string(),
+            depends_on: vec![],
+            executor: "".to_string(),
+            command: "".to_string(),
+            arguments: vec![],
+            on_result: OnResult {
+                terminate_job: vec![],
+                continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
+            },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
+        },
+        run_result: Some(RunResult {
+            duration: Duration::from_secs(20),
+            task_execution_error: None,
+            stdout: None,
+            stderr: None,
+            return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: None,
+            logs: None,
+            previous_attempts: vec![failed_attempt.clone(), failed_attempt],
+        }),
+    };
+
+    let expected = format!("Task '{}' was started at {}\n\
+                            Task '{}' attempt 1 returned 1 after 2.0s, retrying\n\
+                            Task '{}' attempt 2 returned 1 after 2.0s, retrying\n{}{}{}\n",
+                           "copy".cyan(),
+                           dt,
+                           "copy".cyan(),
+                           "copy".cyan(),
+                           "Task '".green(),
+                           "copy".cyan(),
+                           "': succeeded after 20.0s on attempt 3".green());
+    let (result_stdout, result_stderr) = get_task_result_line_str(&retried_task);
+    assert_eq!(result_stdout, expected);
+    assert_eq!(result_stderr, None);
+}
+
+#[test]
+fn test_get_task_result_line_str_signalled() {
+    use chrono::UTC;
+    use factotum::executor::execution_strategy::RunResult;
+    use factotum::factfile::{Task as FactfileTask, OnResult};
+
+    let dt = UTC::now();
+    let signalled_task = Task::<&FactfileTask> {
+        name: String::from("sync"),
+        state: State::Success,
+        run_started: Some(dt),
+        task_spec: &FactfileTask {
+            name: "sync".to_string(),
+            depends_on: vec![],
+            executor: "".to_string(),
+            command: "".to_string(),
+            arguments: vec![],
+            on_result: OnResult {
+                terminate_job: vec![],
+                continue_job: vec![],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![libc::SIGTERM],
+            },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
+        },
+        run_result: Some(RunResult {
+            duration: Duration::from_secs(20),
+            task_execution_error: None,
+            stdout: None,
+            stderr: None,
+            return_code: 128 + libc::SIGTERM,
+            timed_out: false,
+            terminating_signal: Some(libc::SIGTERM),
+            leftover_processes: None,
+            logs: None,
+            previous_attempts: vec![],
+        }),
+    };
+
+    let expected = format!("Task '{}' was started at {}\n{}{}{}\n",
+                           "sync".cyan(),
+                           dt,
+                           "Task '".green(),
+                           "sync".cyan(),
+                           "': succeeded after 20.0s (killed by SIGTERM)".green());
+    let (result_stdout, result_stderr) = get_task_result_line_str(&signalled_task);
+    assert_eq!(result_stdout, expected);
+    assert_eq!(result_stderr, None);
+}
+
+#[test]
+fn test_get_task_result_line_str_leftover_processes() {
+    use chrono::UTC;
+    use factotum::executor::execution_strategy::RunResult;
+    use factotum::factfile::{Task as FactfileTask, OnResult};
+
+    let dt = UTC::now();
+    let untidy_task = Task::<&FactfileTask> {
+        name: String::from("untidy"),
+        state: State::Success,
+        run_started: Some(dt),
+        task_spec: &FactfileTask {
+            name: "untidy".to_string(),
+            depends_on: vec![],
+            executor: "".to_string(),
+            command: "".to_string(),
+            arguments: vec![],
+            on_result: OnResult {
+                terminate_job: vec![],
+                continue_job: vec![0],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
+            },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
+        },
+        run_result: Some(RunResult {
+            duration: Duration::from_secs(20),
+            task_execution_error: None,
+            stdout: None,
+            stderr: Some("a problem".to_string()),
+            return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: Some("factotum killed 1 process(es) the task left running \
+                                      (pids 123)"
+                .to_string()),
+            logs: None,
+            previous_attempts: vec![],
+        }),
+    };
+
+    let expected = format!("Task '{}' was started at {}\n{}{}{}\n{}{}{}\n",
+                           "untidy".cyan(),
+                           dt,
+                           "Task '".green(),
+                           "untidy".cyan(),
+                           "': succeeded after 20.0s".green(),
+                           "Task '".yellow(),
+                           "untidy".cyan(),
+                           "': factotum killed 1 process(es) the task left running (pids 123)"
+                               .yellow());
+    let (result_stdout, result_stderr) = get_task_result_line_str(&untidy_task);
+    assert_eq!(result_stdout, expected);
+    let result_stderr = result_stderr.unwrap();
+    assert!(result_stderr.contains("a problem"));
+    assert!(!result_stderr.contains("left running"));
+}
+
+#[test]
+fn test_get_task_result_line_str_logs() {
+    use chrono::UTC;
+    use factotum::executor::execution_strategy::RunResult;
+    use factotum::factfile::{Task as FactfileTask, OnResult};
+    use std::path::PathBuf;
+
+    let dt = UTC::now();
+    let logged_task = Task::<&FactfileTask> {
+        name: String::from("logged"),
+        state: State::Success,
+        run_started: Some(dt),
+        task_spec: &FactfileTask {
+            name: "logged".to_string(),
+            depends_on: vec![],
+            executor: "".to_string(),
+            command: "".to_string(),
+            arguments: vec![],
+            on_result: OnResult {
+                terminate_job: vec![],
+                continue_job: vec![0],
+                terminate_job_signals: vec![],
+                continue_job_signals: vec![],
+            },
+            timeout: None,
+            retry: None,
+            pool: None,
+            env: HashMap::new(),
+            working_directory: None,
+        },
+        run_result: Some(RunResult {
+            duration: Duration::from_secs(20),
+            task_execution_error: None,
+            stdout: None,
+            stderr: None,
+            return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: None,
+            logs: Some(AttemptLogs {
+                stdout: PathBuf::from("/logs/run/logged.1.out"),
+                stderr: PathBuf::from("/logs/run/logged.1.err"),
+            }),
+            previous_attempts: vec![],
+        }),
+    };
+
+    let expected = format!("Task '{}' was started at {}\n{}{}{}\nTask '{}': the full output \
+                            was saved to '/logs/run/logged.1.out' and \
+                            '/logs/run/logged.1.err'\n",
+                           "logged".cyan(),
+                           dt,
+                           "Task '".green(),
+                           "logged".cyan(),
+                           "': succeeded after 20.0s".green(),
+                           "logged".cyan());
+    let (result_stdout, result_stderr) = get_task_result_line_str(&logged_task);
+    assert_eq!(result_stdout, expected);
+    assert_eq!(result_stderr, None);
+}
+
 #[test]
 fn test_get_task_results_str_summary() {
     use chrono::UTC;
@@ -1206,7 +2190,14 @@ fn test_get_task_results_str_summary() {
         on_result: OnResult {
             terminate_job: vec![],
             continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
         },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
     };
 
     let task_one = Task::<&FactfileTask> {
@@ -1221,6 +2212,11 @@ fn test_get_task_results_str_summary() {
             stdout: Some(String::from("hello world")),
             stderr: Some(String::from("Mistake")),
             return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: None,
+            logs: None,
+            previous_attempts: vec![],
         }),
     };
 
@@ -1234,7 +2230,14 @@ fn test_get_task_results_str_summary() {
         on_result: OnResult {
             terminate_job: vec![],
             continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
         },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
     };
 
     let task_two = Task::<&FactfileTask> {
@@ -1249,6 +2252,11 @@ fn test_get_task_results_str_summary() {
             stdout: Some(String::from("hello world")),
             stderr: Some(String::from("Mistake")),
             return_code: 0,
+            timed_out: false,
+            terminating_signal: None,
+            leftover_processes: None,
+            logs: None,
+            previous_attempts: vec![],
         }),
     };
 
@@ -1287,6 +2295,8 @@ fn test_get_task_results_str_summary() {
 
 #[test]
 fn test_start_task_validation_not_present() {
+    use factotum::factfile::OnResult;
+
     let mut factfile = Factfile::new("N/A", "test");
 
     match validate_start_task(&factfile, "something") {
@@ -1294,7 +2304,24 @@ fn test_start_task_validation_not_present() {
         _ => unreachable!("validation did not fail"),
     }
 
-    factfile.add_task("something", &vec![], "", "", &vec![], &vec![], &vec![]);
+    factfile.add_task_obj(&FactfileTask {
+        name: "something".to_string(),
+        depends_on: vec![],
+        executor: "".to_string(),
+        command: "".to_string(),
+        arguments: vec![],
+        on_result: OnResult {
+            terminate_job: vec![],
+            continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
+        },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
+    }).unwrap();
     if let Err(_) = validate_start_task(&factfile, "something") {
         unreachable!("validation failed when task present")
     }
@@ -1316,7 +2343,14 @@ fn test_start_task_cycles() {
         on_result: OnResult {
             terminate_job: vec![],
             continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
         },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
     };
 
     let task_b = Task {
@@ -1328,7 +2362,14 @@ fn test_start_task_cycles() {
         on_result: OnResult {
             terminate_job: vec![],
             continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
         },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
     };
 
     let task_c = Task {
@@ -1340,7 +2381,14 @@ fn test_start_task_cycles() {
         on_result: OnResult {
             terminate_job: vec![],
             continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
         },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
     };
 
     let task_d = Task {
@@ -1352,13 +2400,20 @@ fn test_start_task_cycles() {
         on_result: OnResult {
             terminate_job: vec![],
             continue_job: vec![],
+            terminate_job_signals: vec![],
+            continue_job_signals: vec![],
         },
+        timeout: None,
+        retry: None,
+        pool: None,
+        env: HashMap::new(),
+        working_directory: None,
     };
 
-    factfile.add_task_obj(&task_a);
-    factfile.add_task_obj(&task_b);
-    factfile.add_task_obj(&task_c);
-    factfile.add_task_obj(&task_d);
+    factfile.add_task_obj(&task_a).unwrap();
+    factfile.add_task_obj(&task_b).unwrap();
+    factfile.add_task_obj(&task_c).unwrap();
+    factfile.add_task_obj(&task_d).unwrap();
 
     match validate_start_task(&factfile, "c") {
         Err(r) => {